@@ -1 +1,299 @@
-pub mod parser;
\ No newline at end of file
+use crate::editor::{Action, KeymapMode, Layout};
+use crate::terminal::event::{parse_notation, Event};
+use crate::terminal::style::Color;
+use parser::{Entry, ParseError, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub mod parser;
+
+const MAX_TAB_WIDTH: usize = 16;
+// In milliseconds.
+const DEFAULT_KEY_TIMEOUT: u64 = 1000;
+const MAX_KEY_TIMEOUT: i64 = 10_000;
+
+#[derive(Clone, Copy)]
+pub struct Style {
+    pub foreground: Color,
+    pub background: Color,
+}
+
+// Colors of what is drawn besides the text, set with `<element>_foreground` and `<element>_background`.
+pub struct Colors {
+    pub status_bar: Style,
+    pub selection: Style,
+    // Questions asked into the status bar.
+    pub prompt: Style,
+    // The path of the file, depending on whether it's saved.
+    pub saved: Style,
+    pub unsaved: Style,
+}
+
+/* Settings of the editor, read from $XDG_CONFIG_HOME/ante/config (or ~/.config/ante/config):
+
+tab_width = 4
+expand_tab = false
+
+[keys]
+layout = "bepo"            # bepo, qwerty, azerty or arrows
+timeout = 1000             # milliseconds waited for the next key of a sequence
+
+[keys.navigation]          # also keys.global (every mode), keys.edition and keys.selection
+"<C-f>" = "search-forward"   # keys written like macros (see `Event::to_notation`), actions named in `Action`
+x = "none"                 # unbinds the key
+"gd" = "delete-word"       # a sequence of keys
+
+[clipboard]
+copy = "xclip -selection clipboard"
+paste = "xclip -selection clipboard -o"
+
+[colors]
+selection_background = "#3a3a3a"    # black, red, green, blue, white, yellow, grey or #rrggbb
+
+[status_bar]
+file_format = true         # tabs or spaces, line endings
+position = false           # row and column of the cursor
+
+[save]
+trim_trailing_whitespace = false
+swap_file = true
+
+A setting which can't be read keeps its default value. */
+pub struct Config {
+    pub tab_width: usize,
+    pub expand_tab: bool,
+    pub layout: Layout,
+    // Bindings changing the default keymap, None unbinding the keys.
+    pub key_bindings: Vec<(KeymapMode, Vec<Event>, Option<Action>)>,
+    // How long a sequence of keys waits for its next key.
+    pub key_timeout: Duration,
+    // Commands reaching the system clipboard, used instead of the detected ones.
+    pub clipboard_copy: Option<String>,
+    pub clipboard_paste: Option<String>,
+    pub colors: Colors,
+    pub show_file_format: bool,
+    pub show_position: bool,
+    // The spaces and tabs ending the rows are removed when saving.
+    pub trim_trailing_whitespace: bool,
+    // Unsaved edits are written to a swap file to be recovered after a crash.
+    pub swap_file: bool,
+}
+
+impl Colors {
+    fn get_mut(&mut self, element: &str) -> Option<&mut Style> {
+        match element {
+            "status_bar" => Some(&mut self.status_bar),
+            "selection" => Some(&mut self.selection),
+            "prompt" => Some(&mut self.prompt),
+            "saved" => Some(&mut self.saved),
+            "unsaved" => Some(&mut self.unsaved),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        let style = |foreground, background| Style { foreground, background };
+        Self {
+            status_bar: style(Color::Black, Color::White),
+            selection: style(Color::Black, Color::White),
+            prompt: style(Color::White, Color::Blue),
+            saved: style(Color::White, Color::Green),
+            unsaved: style(Color::White, Color::Red),
+        }
+    }
+}
+
+impl Config {
+    // The settings of a configuration file, with the errors of what couldn't be read.
+    pub fn parse(text: &str) -> (Self, Vec<ParseError>) {
+        let mut config = Self::default();
+        let (entries, mut errors) = parser::parse(text);
+        for entry in &entries {
+            if let Err(error) = config.apply(entry) {
+                errors.push(error);
+            }
+        }
+        errors.sort_by_key(|error| (error.line, error.col));
+        (config, errors)
+    }
+    /* The settings of the configuration file of the user, the defaults if there isn't any.
+    The errors are given as `path:line:col: message`. */
+    pub fn load() -> (Self, Vec<String>) {
+        let path = match config_path() {
+            Some(path) => path,
+            None => return (Self::default(), Vec::new()),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let (config, errors) = Self::parse(&text);
+                let errors = errors
+                    .iter()
+                    .map(|error| format!("{}:{}", path.display(), error))
+                    .collect();
+                (config, errors)
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => (Self::default(), Vec::new()),
+            Err(error) => (Self::default(), vec![format!("{}: {}", path.display(), error)]),
+        }
+    }
+
+    fn apply(&mut self, entry: &Entry) -> Result<(), ParseError> {
+        let value_error = |message: String| ParseError::new(entry.line, entry.value_col, message);
+        match (entry.section.as_str(), entry.key.as_str()) {
+            ("", "tab_width") => {
+                let width = expect_integer(entry)?;
+                if width < 1 || width > MAX_TAB_WIDTH as i64 {
+                    return Err(value_error(format!("the tab width must be between 1 and {}", MAX_TAB_WIDTH)));
+                }
+                self.tab_width = width as usize;
+            }
+            ("", "expand_tab") => self.expand_tab = expect_boolean(entry)?,
+            ("keys", "layout") => {
+                let name = expect_string(entry)?;
+                self.layout = Layout::from_name(&name).ok_or_else(|| {
+                    value_error(format!("unknown layout `{}`, expected one of: {}", name, Layout::NAMES.join(", ")))
+                })?;
+            }
+            ("keys", "timeout") => {
+                let timeout = expect_integer(entry)?;
+                if !(0..=MAX_KEY_TIMEOUT).contains(&timeout) {
+                    return Err(value_error(format!("the timeout must be between 0 and {} ms", MAX_KEY_TIMEOUT)));
+                }
+                self.key_timeout = Duration::from_millis(timeout as u64);
+            }
+            (section, key) if section.starts_with("keys.") => {
+                let mode_name = &section["keys.".len()..];
+                let mode = KeymapMode::from_name(mode_name).ok_or_else(|| {
+                    ParseError::new(
+                        entry.line,
+                        entry.key_col,
+                        format!("unknown mode `{}` (global, navigation, edition or selection)", mode_name),
+                    )
+                })?;
+                let keys = match parse_notation(key) {
+                    Some(keys) if !keys.is_empty() => keys,
+                    _ => return Err(ParseError::new(entry.line, entry.key_col, format!("invalid key `{}`", key))),
+                };
+                let name = expect_string(entry)?;
+                let action = match name.as_str() {
+                    "none" => None,
+                    _ => Some(
+                        Action::from_name(&name).ok_or_else(|| value_error(format!("unknown action `{}`", name)))?,
+                    ),
+                };
+                self.key_bindings.push((mode, keys, action));
+            }
+            ("clipboard", "copy") => self.clipboard_copy = Some(expect_string(entry)?),
+            ("clipboard", "paste") => self.clipboard_paste = Some(expect_string(entry)?),
+            ("colors", key) if self.color_of(key).is_some() => {
+                let name = expect_string(entry)?;
+                let color = parse_color(&name).ok_or_else(|| value_error(format!("unknown color `{}`", name)))?;
+                if let Some(target) = self.color_of(key) {
+                    *target = color;
+                }
+            }
+            ("status_bar", "file_format") => self.show_file_format = expect_boolean(entry)?,
+            ("status_bar", "position") => self.show_position = expect_boolean(entry)?,
+            ("save", "trim_trailing_whitespace") => self.trim_trailing_whitespace = expect_boolean(entry)?,
+            ("save", "swap_file") => self.swap_file = expect_boolean(entry)?,
+            (section, key) => {
+                let name = if section.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", section, key)
+                };
+                return Err(ParseError::new(entry.line, entry.key_col, format!("unknown setting `{}`", name)));
+            }
+        }
+        Ok(())
+    }
+    // `status_bar_foreground` is the foreground of the status bar.
+    fn color_of(&mut self, key: &str) -> Option<&mut Color> {
+        let (element, side) = key.rsplit_once('_')?;
+        let style = self.colors.get_mut(element)?;
+        match side {
+            "foreground" => Some(&mut style.foreground),
+            "background" => Some(&mut style.background),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            expand_tab: false,
+            layout: Layout::default(),
+            key_bindings: Vec::new(),
+            key_timeout: Duration::from_millis(DEFAULT_KEY_TIMEOUT),
+            clipboard_copy: None,
+            clipboard_paste: None,
+            colors: Colors::default(),
+            show_file_format: true,
+            show_position: false,
+            trim_trailing_whitespace: false,
+            swap_file: true,
+        }
+    }
+}
+
+// $XDG_CONFIG_HOME/ante/config, or ~/.config/ante/config.
+pub fn config_path() -> Option<PathBuf> {
+    let directory = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(directory) if !directory.is_empty() => PathBuf::from(directory),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(directory.join("ante").join("config"))
+}
+
+fn type_error(entry: &Entry, expected: &str) -> ParseError {
+    ParseError::new(
+        entry.line,
+        entry.value_col,
+        format!("expected {}, found {}", expected, entry.value.get_type_name()),
+    )
+}
+fn expect_boolean(entry: &Entry) -> Result<bool, ParseError> {
+    match entry.value {
+        Value::Boolean(boolean) => Ok(boolean),
+        _ => Err(type_error(entry, "a boolean")),
+    }
+}
+fn expect_integer(entry: &Entry) -> Result<i64, ParseError> {
+    match entry.value {
+        Value::Integer(integer) => Ok(integer),
+        _ => Err(type_error(entry, "an integer")),
+    }
+}
+fn expect_string(entry: &Entry) -> Result<String, ParseError> {
+    match &entry.value {
+        Value::String(text) => Ok(text.clone()),
+        _ => Err(type_error(entry, "a string")),
+    }
+}
+
+// A color name, or #rrggbb.
+pub fn parse_color(name: &str) -> Option<Color> {
+    let color = match name.to_lowercase().as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "blue" => Color::Blue,
+        "white" => Color::White,
+        "yellow" => Color::Yellow,
+        "grey" | "gray" => Color::Grey,
+        hex => {
+            let hex = hex.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.is_ascii())?;
+            let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            Color::Rgb {
+                red: component(0)?,
+                green: component(2)?,
+                blue: component(4)?,
+            }
+        }
+    };
+    Some(color)
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+
+/* Reads the subset of TOML the configuration needs: `[section]` headers, `key = value` lines
+with a string ("..."), an integer or a boolean as value, and # comments. A key is either bare (letters, digits,
+_ and -) or a string, to write keys like "<C-s>". */
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+}
+
+// One `key = value` line. Lines and columns start at 1, the columns count chars.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entry {
+    // Empty before the first header.
+    pub section: String,
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+    pub key_col: usize,
+    pub value_col: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, col: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            col,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+impl Value {
+    pub fn get_type_name(&self) -> &str {
+        match self {
+            Value::Boolean(_) => "a boolean",
+            Value::Integer(_) => "an integer",
+            Value::String(_) => "a string",
+        }
+    }
+}
+
+// The entries which could be read, and an error for every line which couldn't.
+pub fn parse(text: &str) -> (Vec<Entry>, Vec<ParseError>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    let mut section = String::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let chars: Vec<char> = line.chars().collect();
+        let mut col = skip_spaces(&chars, 0);
+        match chars.get(col) {
+            None | Some('#') => continue,
+            Some('[') => match parse_header(&chars, col) {
+                Ok(name) => section = name,
+                Err((col, message)) => errors.push(ParseError::new(line_number, col + 1, message)),
+            },
+            Some(_) => {
+                let key_col = col;
+                let key = if chars[col] == '"' {
+                    match parse_string(&chars, col) {
+                        Ok((key, end)) => {
+                            col = end;
+                            key
+                        }
+                        Err((col, message)) => {
+                            errors.push(ParseError::new(line_number, col + 1, message));
+                            continue;
+                        }
+                    }
+                } else {
+                    while col < chars.len() && is_bare_key_char(chars[col]) {
+                        col += 1;
+                    }
+                    chars[key_col..col].iter().collect()
+                };
+                col = skip_spaces(&chars, col);
+                if key.is_empty() || chars.get(col) != Some(&'=') {
+                    errors.push(ParseError::new(line_number, col + 1, "expected `key = value`"));
+                    continue;
+                }
+                let value_col = skip_spaces(&chars, col + 1);
+                match parse_value(&chars, value_col) {
+                    Ok(value) => entries.push(Entry {
+                        section: section.clone(),
+                        key,
+                        value,
+                        line: line_number,
+                        key_col: key_col + 1,
+                        value_col: value_col + 1,
+                    }),
+                    Err((col, message)) => errors.push(ParseError::new(line_number, col + 1, message)),
+                }
+            }
+        }
+    }
+    (entries, errors)
+}
+
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+fn skip_spaces(chars: &[char], mut col: usize) -> usize {
+    while col < chars.len() && (chars[col] == ' ' || chars[col] == '\t') {
+        col += 1;
+    }
+    col
+}
+// Only spaces and a comment may follow what was read.
+fn check_line_end(chars: &[char], col: usize) -> Result<(), (usize, String)> {
+    let col = skip_spaces(chars, col);
+    match chars.get(col) {
+        None | Some('#') => Ok(()),
+        Some(c) => Err((col, format!("unexpected `{}`", c))),
+    }
+}
+
+// The errors are given with the index of the char they are at.
+fn parse_header(chars: &[char], start: usize) -> Result<String, (usize, String)> {
+    let name_col = skip_spaces(chars, start + 1);
+    let mut col = name_col;
+    while col < chars.len() && (is_bare_key_char(chars[col]) || chars[col] == '.') {
+        col += 1;
+    }
+    let name: String = chars[name_col..col].iter().collect();
+    col = skip_spaces(chars, col);
+    if name.is_empty() || chars.get(col) != Some(&']') {
+        return Err((col, String::from("expected `[section]`")));
+    }
+    check_line_end(chars, col + 1)?;
+    Ok(name)
+}
+// A string starting at `start`, with the index of the char following it.
+fn parse_string(chars: &[char], start: usize) -> Result<(String, usize), (usize, String)> {
+    let mut text = String::new();
+    let mut col = start + 1;
+    loop {
+        match chars.get(col) {
+            None => return Err((start, String::from("unterminated string"))),
+            Some('"') => return Ok((text, col + 1)),
+            Some('\\') => {
+                text.push(match chars.get(col + 1) {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    _ => return Err((col, String::from("unknown escape sequence"))),
+                });
+                col += 1;
+            }
+            Some(c) => text.push(*c),
+        }
+        col += 1;
+    }
+}
+fn parse_value(chars: &[char], start: usize) -> Result<Value, (usize, String)> {
+    if chars.get(start) == Some(&'"') {
+        let (text, end) = parse_string(chars, start)?;
+        check_line_end(chars, end)?;
+        return Ok(Value::String(text));
+    }
+    let mut end = start;
+    while end < chars.len() && !chars[end].is_whitespace() && chars[end] != '#' {
+        end += 1;
+    }
+    let word: String = chars[start..end].iter().collect();
+    let value = match word.as_str() {
+        "" => return Err((start, String::from("missing value"))),
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        _ => match word.replace('_', "").parse::<i64>() {
+            Ok(integer) => Value::Integer(integer),
+            Err(_) => return Err((start, format!("invalid value `{}` (strings are written between quotes)", word))),
+        },
+    };
+    check_line_end(chars, end)?;
+    Ok(value)
+}
@@ -0,0 +1,155 @@
+use crate::terminal::Terminal;
+use std::ffi::OsString;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/* The clipboard of the system, shared with the other programs. The copies are sent to it as well
+as to the internal registers, the register '+' pastes from it. */
+pub trait ClipboardProvider {
+    fn get_name(&self) -> &str;
+    fn copy(&mut self, text: &str, terminal: &mut Terminal) -> std::io::Result<()>;
+    // None if the clipboard can't be read: the internal register is pasted instead.
+    fn paste(&mut self) -> Option<String>;
+}
+
+/* Asks the terminal to put the text into the clipboard, with the OSC 52 escape sequence.
+It goes through SSH, so the copy reaches the clipboard of the computer the user sits at.
+tmux forwards it with `set -g set-clipboard on`. Most terminals refuse to let it be read. */
+pub struct Osc52;
+
+// Local programs writing to and reading from the clipboard, like xclip or wl-copy.
+pub struct CommandClipboard {
+    copy_command: Vec<String>,
+    paste_command: Option<Vec<String>>,
+}
+
+impl ClipboardProvider for Osc52 {
+    fn get_name(&self) -> &str {
+        "OSC 52"
+    }
+    fn copy(&mut self, text: &str, terminal: &mut Terminal) -> std::io::Result<()> {
+        terminal.print(format!("\x1b]52;c;{}\x07", encode_base64(text.as_bytes())));
+        terminal.flush();
+        Ok(())
+    }
+    fn paste(&mut self) -> Option<String> {
+        None
+    }
+}
+
+impl CommandClipboard {
+    // constructor
+    // The commands are split on whitespace, the first word being the program.
+    pub fn new(copy_command: &str, paste_command: Option<&str>) -> Self {
+        let split = |command: &str| command.split_whitespace().map(String::from).collect::<Vec<String>>();
+        Self {
+            copy_command: split(copy_command),
+            paste_command: paste_command.map(split).filter(|command| !command.is_empty()),
+        }
+    }
+}
+
+impl ClipboardProvider for CommandClipboard {
+    fn get_name(&self) -> &str {
+        self.copy_command.first().map_or("", |program| program.as_str())
+    }
+    // The text is written to the standard input of the command.
+    fn copy(&mut self, text: &str, _terminal: &mut Terminal) -> std::io::Result<()> {
+        let (program, args) = self
+            .copy_command
+            .split_first()
+            .ok_or_else(|| std::io::Error::other("no copy command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(std::io::Error::other(format!("{} failed ({})", program, status)))
+        }
+    }
+    fn paste(&mut self) -> Option<String> {
+        let (program, args) = self.paste_command.as_ref()?.split_first()?;
+        let output = Command::new(program).args(args).stderr(Stdio::null()).output().ok()?;
+        if output.status.success() {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            None
+        }
+    }
+}
+
+/* Chooses how to reach the clipboard of the system:
+- the commands given by the ANTE_CLIPBOARD (copy) and ANTE_CLIPBOARD_PASTE (paste) environment variables,
+- the commands of the configuration,
+- OSC 52 in an SSH session, where a local command would only reach the clipboard of the remote computer,
+- wl-copy under Wayland, xclip or xsel under X11, pbcopy on macOS,
+- OSC 52 in tmux.
+None if there isn't any: the copies only go to the internal registers.
+The environment variables are read through `env`, `std::env::var_os` outside of the tests. */
+pub fn detect(
+    copy_command: Option<&str>,
+    paste_command: Option<&str>,
+    env: impl Fn(&str) -> Option<OsString>,
+) -> Option<Box<dyn ClipboardProvider>> {
+    let is_set = |name: &str| env(name).is_some_and(|value| !value.is_empty());
+    let is_in_path = |program: &str| {
+        env("PATH")
+            .is_some_and(|paths| std::env::split_paths(&paths).any(|directory| directory.join(program).is_file()))
+    };
+    if let Some(copy_command) = env("ANTE_CLIPBOARD").and_then(|command| command.into_string().ok()) {
+        if !copy_command.trim().is_empty() {
+            let paste_command = env("ANTE_CLIPBOARD_PASTE").and_then(|command| command.into_string().ok());
+            return Some(Box::new(CommandClipboard::new(&copy_command, paste_command.as_deref())));
+        }
+    }
+    if let Some(copy_command) = copy_command.filter(|command| !command.trim().is_empty()) {
+        return Some(Box::new(CommandClipboard::new(copy_command, paste_command)));
+    }
+    if is_set("SSH_TTY") || is_set("SSH_CONNECTION") {
+        return Some(Box::new(Osc52));
+    }
+    let local_commands = [
+        ("WAYLAND_DISPLAY", "wl-copy", "wl-paste --no-newline"),
+        ("DISPLAY", "xclip -selection clipboard", "xclip -selection clipboard -o"),
+        ("DISPLAY", "xsel --clipboard --input", "xsel --clipboard --output"),
+    ];
+    for (display, copy_command, paste_command) in local_commands {
+        if is_set(display) && is_in_path(copy_command.split(' ').next().unwrap_or_default()) {
+            return Some(Box::new(CommandClipboard::new(copy_command, Some(paste_command))));
+        }
+    }
+    if cfg!(target_os = "macos") && is_in_path("pbcopy") {
+        return Some(Box::new(CommandClipboard::new("pbcopy", Some("pbpaste"))));
+    }
+    if is_set("TMUX") {
+        return Some(Box::new(Osc52));
+    }
+    None
+}
+
+// Standard base64 with padding, as OSC 52 expects it.
+pub fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
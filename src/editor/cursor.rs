@@ -11,10 +11,10 @@ impl Cursor {
 
     // accessors
     pub fn get_col(&self) -> usize {
-        self.col.clone()
+        self.col
     }
     pub fn get_row(&self) -> usize {
-        self.row.clone()
+        self.row
     }
     pub fn set_col(&mut self, value: usize) {
         self.col = value;
@@ -24,3 +24,9 @@ impl Cursor {
         self.row = row;
     }
 }
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,67 @@
+// Beyond this number of compared line pairs, the changed lines are listed without being matched.
+const MAX_LCS_TABLE_SIZE: usize = 4_000_000;
+
+#[derive(PartialEq, Debug)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/* Line based diff between two texts. The common beginning and end are trimmed,
+then the remaining lines are matched with a longest common subsequence. */
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut diff: Vec<DiffLine> = old[..prefix].iter().map(|line| DiffLine::Same(line)).collect();
+    if (old_middle.len() + 1) * (new_middle.len() + 1) <= MAX_LCS_TABLE_SIZE {
+        diff.append(&mut diff_middle(old_middle, new_middle));
+    } else {
+        diff.extend(old_middle.iter().map(|line| DiffLine::Removed(line)));
+        diff.extend(new_middle.iter().map(|line| DiffLine::Added(line)));
+    }
+    diff.extend(old[old.len() - suffix..].iter().map(|line| DiffLine::Same(line)));
+    diff
+}
+
+fn diff_middle<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // lcs[i][j]: lenght of the longest common subsequence of old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|line| DiffLine::Removed(line)));
+    diff.extend(new[j..].iter().map(|line| DiffLine::Added(line)));
+    diff
+}
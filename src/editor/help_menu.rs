@@ -1,4 +1,73 @@
-pub const HELP_MENU_CONTENT: &str = "- Shortcuts\n\r
-Ctrl + c: quit\r
-Ctrl + s: save/save as\r
-Ctrl + h: open/close help menu";
+use super::keymap::{Keymap, KeymapMode};
+use crate::terminal::event::{Event, Key};
+use crate::terminal::style::Color;
+
+// The sections of the help menu, with what the keys don't tell.
+const SECTIONS: [(KeymapMode, &str, &str); 4] = [
+    (KeymapMode::Global, "Shortcuts", ""),
+    (
+        KeymapMode::Navigation,
+        "Navigation mode",
+        "A count typed before a command repeats it, or gives it a number of rows or of replays",
+    ),
+    (KeymapMode::Edition, "Edition mode", "The other keys are typed at every cursor"),
+    (KeymapMode::Selection, "Selection mode", ""),
+];
+
+// The help menu is made from the keymap, so that it shows the keys the user has bound.
+pub fn help_menu_lines(keymap: &Keymap) -> Vec<(String, Color)> {
+    let mut lines = Vec::new();
+    for (mode, title, note) in SECTIONS {
+        if !lines.is_empty() {
+            lines.push((String::new(), Color::White));
+        }
+        lines.push((format!("- {}", title), Color::Yellow));
+        if !note.is_empty() {
+            lines.push((note.to_string(), Color::Grey));
+        }
+        for (action, sequences) in keymap.get_actions_of(mode) {
+            let keys: Vec<String> = sequences.into_iter().map(describe_sequence).collect();
+            lines.push((format!("{}: {}", keys.join(", "), action.get_description()), Color::White));
+        }
+    }
+    lines
+}
+
+// The keys of a sequence one after the other: `g g`, `Ctrl + x Ctrl + s`.
+fn describe_sequence(keys: &[Event]) -> String {
+    keys.iter().map(describe_key).collect::<Vec<String>>().join(" ")
+}
+// How a key is written for the user: `v`, `Ctrl + s`, `Alt + Shift + Down arrow`.
+fn describe_key(event: &Event) -> String {
+    let (key, modifiers) = match event.get_key() {
+        Some(key) => key,
+        None => return String::new(),
+    };
+    let name = match key {
+        Key::Char(' ') => String::from("Space"),
+        Key::Char(c) => c.to_string(),
+        Key::Backspace => String::from("Backspace"),
+        Key::Enter => String::from("Enter"),
+        Key::LeftArrow => String::from("Left arrow"),
+        Key::RightArrow => String::from("Right arrow"),
+        Key::UpArrow => String::from("Up arrow"),
+        Key::DownArrow => String::from("Down arrow"),
+        Key::Tab => String::from("Tab"),
+        Key::BackTab => String::from("Shift + Tab"),
+        Key::Esc => String::from("Esc"),
+        Key::Home => String::from("Home"),
+        Key::End => String::from("End"),
+        Key::PageUp => String::from("Page up"),
+        Key::PageDown => String::from("Page down"),
+        Key::Delete => String::from("Delete"),
+        Key::Insert => String::from("Insert"),
+        Key::F(number) => format!("F{}", number),
+    };
+    format!(
+        "{}{}{}{}",
+        if modifiers.ctrl { "Ctrl + " } else { "" },
+        if modifiers.alt { "Alt + " } else { "" },
+        if modifiers.shift { "Shift + " } else { "" },
+        name
+    )
+}
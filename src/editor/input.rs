@@ -0,0 +1,124 @@
+use super::keymap::{Action, Resolution};
+use super::Editor;
+use crate::terminal::event::{Event, Key};
+use std::time::{Duration, Instant};
+
+/* Keys typed so far of a sequence bound in the keymap, like `g` waiting for the second `g`.
+A sequence is resolved as soon as it can't become a longer binding. When it isn't bound at all,
+its first key is processed alone and the next ones are typed again. When the next key takes
+longer than the timeout, the keys are resolved as they are. */
+pub struct PendingKeys {
+    keys: Vec<Event>,
+    last_key_time: Instant,
+    timeout: Duration,
+    // Keys of the binding run last.
+    resolved: Vec<Event>,
+}
+
+impl PendingKeys {
+    // constructor
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            keys: Vec::new(),
+            last_key_time: Instant::now(),
+            timeout,
+            resolved: Vec::new(),
+        }
+    }
+
+    // accessors
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+    // The keys as they are written in macros, to be shown into the status bar.
+    pub fn get_notation(&self) -> String {
+        self.keys.iter().filter_map(Event::to_notation).collect()
+    }
+    // Time left before the keys are resolved, None if there aren't any.
+    pub fn get_time_left(&self) -> Option<Duration> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        Some(self.timeout.saturating_sub(self.last_key_time.elapsed()))
+    }
+    pub fn get_resolved(&self) -> &[Event] {
+        &self.resolved
+    }
+
+    fn push(&mut self, event: Event) {
+        self.keys.push(event);
+        self.last_key_time = Instant::now();
+    }
+    fn take(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.keys)
+    }
+}
+
+impl Editor {
+    // Adds the key to the pending sequence, and runs what the sequence is bound to once it is known.
+    pub(super) fn key_pressed(&mut self, event: Event) {
+        self.pending_keys.push(event);
+        let keys = self.pending_keys.keys.clone();
+        match self.keymap.resolve(self.get_keymap_mode(), &keys) {
+            Resolution::Action(action) => {
+                self.pending_keys.take();
+                self.run_bound_action(keys, action);
+            }
+            // Waiting for the next key.
+            Resolution::Prefix(_) => (),
+            Resolution::None => {
+                self.pending_keys.take();
+                self.process_keys_apart(keys);
+            }
+        }
+    }
+    // The next key didn't come in time: the pending keys run what they are bound to, if anything.
+    pub(super) fn resolve_pending_keys(&mut self) {
+        let keys = self.pending_keys.take();
+        match self.keymap.get(self.get_keymap_mode(), &keys) {
+            Some(action) => self.run_bound_action(keys, action),
+            None => self.process_keys_apart(keys),
+        }
+    }
+    /* The keys don't make a binding: the first one is processed alone, then the next ones are
+    pressed again, as they may start another sequence. The first mode change applies to them. */
+    fn process_keys_apart(&mut self, keys: Vec<Event>) {
+        let (first, next) = match keys.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        match self.keymap.get(self.get_keymap_mode(), std::slice::from_ref(first)) {
+            Some(action) => self.run_bound_action(vec![first.clone()], action),
+            None => self.unbound_key_pressed(first.clone()),
+        }
+        for event in next {
+            self.key_pressed(event.clone());
+        }
+    }
+    fn run_bound_action(&mut self, keys: Vec<Event>, action: Action) {
+        self.pending_keys.resolved = keys;
+        self.run_action_with_count(action);
+    }
+    /* In navigation mode, digits which aren't bound make a count for the next action, 0 only after another digit.
+    In edition mode, chars which aren't bound are typed at every cursor. */
+    fn unbound_key_pressed(&mut self, event: Event) {
+        match event {
+            Event::KeyPressed(Key::Char(digit @ '0'..='9'))
+                if self.current_mode.is_navigation_mode() && (digit != '0' || self.pending_count.is_some()) =>
+            {
+                let digit = digit as usize - '0' as usize;
+                self.pending_count = Some(self.pending_count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            }
+            Event::KeyPressed(Key::Char(c)) if self.current_mode.is_edition_mode() => {
+                self.at_every_cursor(|editor| {
+                    editor.text_buffer.insert_char(
+                        editor.current_col_position(),
+                        editor.current_row_position(),
+                        c);
+                    editor.move_cursor_right(); // double vérif de current col pos, à revoir
+                })
+            }
+            _ => self.pending_count = None,
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use super::layout::{Layout, Motion};
+use crate::terminal::event::{Event, Key};
+
+// What a key can be bound to. Every action has a name, used by the configuration to bind keys.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Move(Motion),
+    // modes
+    EditionMode,
+    NavigationMode,
+    SelectChars,
+    SelectLines,
+    SelectBlock,
+    // files
+    Quit,
+    Save,
+    OpenFile,
+    ToggleHelp,
+    // history
+    Undo,
+    Redo,
+    // rows
+    GoToFirstRow,
+    GoToLastRow,
+    // registers
+    ChooseRegister,
+    PasteAfter,
+    PasteBefore,
+    PasteAtCursors,
+    CopyRows,
+    CutRows,
+    // macros
+    RecordMacro,
+    ReplayMacro,
+    // multiple cursors
+    AddCursorBelow,
+    AddCursorAbove,
+    AddCursorAtNextOccurrence,
+    AddCursorsAtMatches,
+    ClearCursors,
+    // search
+    SearchForward,
+    SearchBackward,
+    SearchNext,
+    SearchPrevious,
+    Replace,
+    // file format, indentation
+    ToggleLineEnding,
+    ToggleFinalNewline,
+    ToggleExpandTab,
+    CycleTabWidth,
+    // edition
+    InsertTab,
+    DeleteBackward,
+    DeleteForward,
+    Unindent,
+    NewLine,
+    DeleteWord,
+    // selection
+    CopySelection,
+    CutSelection,
+    DeleteSelection,
+    ReplaceSelection,
+    PasteOverSelection,
+}
+
+// The actions with their names and what they do, as the help menu tells it.
+const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::Move(Motion::Down), "move-down", "move down"),
+    (Action::Move(Motion::Up), "move-up", "move up"),
+    (Action::Move(Motion::Left), "move-left", "move left"),
+    (Action::Move(Motion::Right), "move-right", "move right"),
+    (Action::Move(Motion::RowStart), "move-to-row-start", "go to the start of the row"),
+    (Action::Move(Motion::RowEnd), "move-to-row-end", "go to the end of the row"),
+    (Action::Move(Motion::PageDown), "page-down", "move down a screen"),
+    (Action::Move(Motion::PageUp), "page-up", "move up a screen"),
+    (Action::EditionMode, "edition-mode", "edition mode"),
+    (Action::NavigationMode, "navigation-mode", "back to the navigation mode"),
+    (Action::SelectChars, "select-chars", "select characters, or change the kind of selection, or leave it"),
+    (Action::SelectLines, "select-lines", "select lines, or change the kind of selection, or leave it"),
+    (Action::SelectBlock, "select-block", "select a block, or change the kind of selection, or leave it"),
+    (Action::Quit, "quit", "quit"),
+    (Action::Save, "save", "save/save as"),
+    (Action::OpenFile, "open-file", "open a file"),
+    (Action::ToggleHelp, "toggle-help", "open/close help menu"),
+    (Action::Undo, "undo", "undo"),
+    (Action::Redo, "redo", "redo"),
+    (Action::GoToFirstRow, "go-to-first-row", "go to the first row, or to the row of the count"),
+    (Action::GoToLastRow, "go-to-last-row", "go to the last row, or to the row of the count"),
+    (
+        Action::ChooseRegister,
+        "choose-register",
+        "choose the register of the next copy, cut or paste (a-z, 0-9: recent copies, +: system clipboard)",
+    ),
+    (Action::PasteAfter, "paste-after", "paste after the cursor"),
+    (Action::PasteBefore, "paste-before", "paste before the cursor"),
+    (Action::PasteAtCursors, "paste-at-cursors", "paste at every cursor"),
+    (Action::CopyRows, "copy-rows", "copy the row"),
+    (Action::CutRows, "cut-rows", "cut the row"),
+    (Action::RecordMacro, "record-macro", "record a macro into a register (a-z), again to stop"),
+    (Action::ReplayMacro, "replay-macro", "replay a macro (a-z, @: the last one)"),
+    (Action::AddCursorBelow, "add-cursor-below", "add a cursor on the row below"),
+    (Action::AddCursorAbove, "add-cursor-above", "add a cursor on the row above"),
+    (
+        Action::AddCursorAtNextOccurrence,
+        "add-cursor-at-next-occurrence",
+        "add a cursor at the next occurrence of the word under the cursor",
+    ),
+    (Action::AddCursorsAtMatches, "add-cursors-at-matches", "put a cursor at every match of the last search"),
+    (Action::ClearCursors, "clear-cursors", "keep only one cursor"),
+    (
+        Action::SearchForward,
+        "search-forward",
+        "search forward (Ctrl + r: regex, Ctrl + t: case sensitivity)",
+    ),
+    (Action::SearchBackward, "search-backward", "search backward"),
+    (Action::SearchNext, "search-next", "next match"),
+    (Action::SearchPrevious, "search-previous", "previous match"),
+    (Action::Replace, "replace", "search and replace"),
+    (Action::ToggleLineEnding, "toggle-line-ending", "switch line endings between LF and CRLF"),
+    (Action::ToggleFinalNewline, "toggle-final-newline", "add/remove the final newline"),
+    (
+        Action::ToggleExpandTab,
+        "toggle-expand-tab",
+        "switch the tab key between a tab character and spaces",
+    ),
+    (Action::CycleTabWidth, "cycle-tab-width", "change the tab width (2, 4 or 8)"),
+    (Action::InsertTab, "insert-tab", "insert a tab, or spaces up to the next tab stop"),
+    (Action::DeleteBackward, "delete-backward", "delete before the cursor"),
+    (Action::DeleteForward, "delete-forward", "delete after the cursor"),
+    (Action::Unindent, "unindent", "remove a level of indentation from the row"),
+    (Action::NewLine, "new-line", "split the row"),
+    (Action::DeleteWord, "delete-word", "delete up to the next word"),
+    (Action::CopySelection, "copy-selection", "copy"),
+    (Action::CutSelection, "cut-selection", "cut"),
+    (Action::DeleteSelection, "delete-selection", "delete"),
+    (Action::ReplaceSelection, "replace-selection", "replace the selection"),
+    (Action::PasteOverSelection, "paste-over-selection", "paste over the selection"),
+];
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS.iter().find(|(_, action_name, _)| *action_name == name).map(|(action, _, _)| *action)
+    }
+    pub fn get_name(&self) -> &'static str {
+        ACTIONS.iter().find(|(action, _, _)| action == self).map_or("", |(_, name, _)| name)
+    }
+    pub fn get_description(&self) -> &'static str {
+        ACTIONS
+            .iter()
+            .find(|(action, _, _)| action == self)
+            .map_or("", |(_, _, description)| description)
+    }
+    // A count typed before the action repeats it.
+    pub fn is_repeatable(&self) -> bool {
+        matches!(
+            self,
+            Action::Move(_)
+                | Action::SearchNext
+                | Action::SearchPrevious
+                | Action::PasteAfter
+                | Action::PasteBefore
+                | Action::AddCursorBelow
+                | Action::AddCursorAbove
+                | Action::DeleteWord
+                | Action::DeleteForward
+                | Action::Undo
+                | Action::Redo
+        )
+    }
+}
+
+/* Where a binding applies. The global bindings apply in every mode, after the ones of the mode:
+a mode may bind a key to something else. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeymapMode {
+    Global,
+    Navigation,
+    Edition,
+    Selection,
+}
+
+impl KeymapMode {
+    pub const ALL: [KeymapMode; 4] =
+        [KeymapMode::Global, KeymapMode::Navigation, KeymapMode::Edition, KeymapMode::Selection];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|mode| mode.get_name() == name)
+    }
+    pub fn get_name(&self) -> &str {
+        match self {
+            KeymapMode::Global => "global",
+            KeymapMode::Navigation => "navigation",
+            KeymapMode::Edition => "edition",
+            KeymapMode::Selection => "selection",
+        }
+    }
+}
+
+/* The keys of every mode and what they do. In edition mode, the chars which aren't bound are typed.
+A binding is a sequence of keys, `g g` or `Ctrl + x Ctrl + s`, most of them are a single key.
+The bindings are kept in the order they were made, the one of the help menu. */
+pub struct Keymap {
+    bindings: Vec<(KeymapMode, Vec<Event>, Action)>,
+}
+
+// What the keys typed so far are bound to (see `Keymap::resolve`).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Resolution {
+    Action(Action),
+    // The start of longer sequences, and the action of the keys themselves if they are bound too.
+    Prefix(Option<Action>),
+    None,
+}
+
+impl Keymap {
+    // constructor
+    // The default bindings, with the motion keys of the layout.
+    pub fn new(layout: Layout) -> Self {
+        let mut keymap = Self { bindings: Vec::new() };
+        let ctrl = |c| Event::CtrlKeyPressed(Key::Char(c));
+        let key = |c| Event::KeyPressed(Key::Char(c));
+        let global = [
+            (vec![ctrl('c')], Action::Quit),
+            (vec![ctrl('s')], Action::Save),
+            (vec![ctrl('x'), ctrl('s')], Action::Save),
+            (vec![ctrl('o')], Action::OpenFile),
+            // for some reasons ctrl + backspace opens also the menu
+            (vec![ctrl('h')], Action::ToggleHelp),
+            (vec![ctrl('z')], Action::Undo),
+            (vec![ctrl('y')], Action::Redo),
+            (vec![ctrl('r')], Action::Replace),
+            (vec![ctrl('v')], Action::SelectBlock),
+            (vec![ctrl('d')], Action::AddCursorAtNextOccurrence),
+            (vec![Event::CtrlKeyPressed(Key::DownArrow)], Action::AddCursorBelow),
+            (vec![Event::CtrlKeyPressed(Key::UpArrow)], Action::AddCursorAbove),
+            (vec![Event::KeyPressed(Key::DownArrow)], Action::Move(Motion::Down)),
+            (vec![Event::KeyPressed(Key::UpArrow)], Action::Move(Motion::Up)),
+            (vec![Event::KeyPressed(Key::LeftArrow)], Action::Move(Motion::Left)),
+            (vec![Event::KeyPressed(Key::RightArrow)], Action::Move(Motion::Right)),
+            (vec![Event::KeyPressed(Key::Home)], Action::Move(Motion::RowStart)),
+            (vec![Event::KeyPressed(Key::End)], Action::Move(Motion::RowEnd)),
+            (vec![Event::KeyPressed(Key::PageDown)], Action::Move(Motion::PageDown)),
+            (vec![Event::KeyPressed(Key::PageUp)], Action::Move(Motion::PageUp)),
+            (vec![Event::CtrlKeyPressed(Key::Home)], Action::GoToFirstRow),
+            (vec![Event::CtrlKeyPressed(Key::End)], Action::GoToLastRow),
+            (vec![Event::KeyPressed(Key::F(1))], Action::ToggleHelp),
+        ];
+        let motions: Vec<(Vec<Event>, Action)> = layout
+            .get_motion_letters()
+            .into_iter()
+            .map(|(letter, motion)| (vec![key(letter)], Action::Move(motion)))
+            .collect();
+        let navigation = [
+            (vec![key('e')], Action::EditionMode),
+            (vec![Event::KeyPressed(Key::Insert)], Action::EditionMode),
+            (vec![key('v')], Action::SelectChars),
+            (vec![key('V')], Action::SelectLines),
+            (vec![key('g'), key('g')], Action::GoToFirstRow),
+            (vec![key('G')], Action::GoToLastRow),
+            (vec![key('"')], Action::ChooseRegister),
+            (vec![key('p')], Action::PasteAfter),
+            (vec![key('P')], Action::PasteBefore),
+            (vec![key('Y')], Action::CopyRows),
+            (vec![key('D')], Action::CutRows),
+            (vec![key('d'), key('w')], Action::DeleteWord),
+            (vec![Event::KeyPressed(Key::Delete)], Action::DeleteForward),
+            (vec![key('q')], Action::RecordMacro),
+            (vec![key('@')], Action::ReplayMacro),
+            (vec![key('A')], Action::AddCursorsAtMatches),
+            (vec![Event::KeyPressed(Key::Esc)], Action::ClearCursors),
+            (vec![key('/')], Action::SearchForward),
+            (vec![key('?')], Action::SearchBackward),
+            (vec![key('n')], Action::SearchNext),
+            (vec![key('N')], Action::SearchPrevious),
+            (vec![key('L')], Action::ToggleLineEnding),
+            (vec![key('F')], Action::ToggleFinalNewline),
+            (vec![key('T')], Action::ToggleExpandTab),
+            (vec![key('W')], Action::CycleTabWidth),
+        ];
+        let edition = [
+            (vec![Event::KeyPressed(Key::Tab)], Action::InsertTab),
+            (vec![Event::KeyPressed(Key::BackTab)], Action::Unindent),
+            (vec![Event::KeyPressed(Key::Backspace)], Action::DeleteBackward),
+            (vec![Event::KeyPressed(Key::Delete)], Action::DeleteForward),
+            (vec![Event::KeyPressed(Key::Enter)], Action::NewLine),
+            (vec![ctrl('v')], Action::PasteAtCursors),
+            (vec![Event::KeyPressed(Key::Esc)], Action::NavigationMode),
+        ];
+        let selection = [
+            (vec![key('v')], Action::SelectChars),
+            (vec![key('V')], Action::SelectLines),
+            (vec![key('y')], Action::CopySelection),
+            (vec![key('x')], Action::CutSelection),
+            (vec![key('d')], Action::DeleteSelection),
+            (vec![Event::KeyPressed(Key::Delete)], Action::DeleteSelection),
+            (vec![key('R')], Action::ReplaceSelection),
+            (vec![key('p')], Action::PasteOverSelection),
+            (vec![key('"')], Action::ChooseRegister),
+            (vec![Event::KeyPressed(Key::Esc)], Action::NavigationMode),
+        ];
+        for (keys, action) in global {
+            keymap.bind(KeymapMode::Global, keys, Some(action));
+        }
+        for (keys, action) in motions.iter().cloned().chain(navigation) {
+            keymap.bind(KeymapMode::Navigation, keys, Some(action));
+        }
+        for (keys, action) in edition {
+            keymap.bind(KeymapMode::Edition, keys, Some(action));
+        }
+        for (keys, action) in motions.into_iter().chain(selection) {
+            keymap.bind(KeymapMode::Selection, keys, Some(action));
+        }
+        keymap
+    }
+
+    // Binds the keys to the action in the mode, replacing what they were bound to. None unbinds them.
+    pub fn bind(&mut self, mode: KeymapMode, keys: Vec<Event>, action: Option<Action>) {
+        match self.bindings.iter().position(|(bound_mode, bound_keys, _)| *bound_mode == mode && *bound_keys == keys) {
+            Some(index) => match action {
+                Some(action) => self.bindings[index].2 = action,
+                None => {
+                    self.bindings.remove(index);
+                }
+            },
+            None => {
+                if let Some(action) = action {
+                    self.bindings.push((mode, keys, action));
+                }
+            }
+        }
+    }
+    // The binding of the mode if there is one, else the global one.
+    pub fn get(&self, mode: KeymapMode, keys: &[Event]) -> Option<Action> {
+        let find = |mode: KeymapMode| {
+            self.bindings
+                .iter()
+                .find(|(bound_mode, bound_keys, _)| *bound_mode == mode && bound_keys == keys)
+                .map(|(_, _, action)| *action)
+        };
+        find(mode).or_else(|| find(KeymapMode::Global))
+    }
+    /* Whether the keys typed so far are bound, or may still become a binding with the next keys.
+    When they are both, the input waits for the next key or for the timeout (see `input`). */
+    pub fn resolve(&self, mode: KeymapMode, keys: &[Event]) -> Resolution {
+        let is_prefix = self.bindings.iter().any(|(bound_mode, bound_keys, _)| {
+            (*bound_mode == mode || *bound_mode == KeymapMode::Global)
+                && bound_keys.len() > keys.len()
+                && bound_keys.starts_with(keys)
+        });
+        match (self.get(mode, keys), is_prefix) {
+            (action, true) => Resolution::Prefix(action),
+            (Some(action), false) => Resolution::Action(action),
+            (None, false) => Resolution::None,
+        }
+    }
+    // The actions bound in the mode, each one with its key sequences, in the order of the bindings.
+    pub fn get_actions_of(&self, mode: KeymapMode) -> Vec<(Action, Vec<&[Event]>)> {
+        let mut actions: Vec<(Action, Vec<&[Event]>)> = Vec::new();
+        for (_, keys, action) in self.bindings.iter().filter(|(bound_mode, _, _)| *bound_mode == mode) {
+            match actions.iter_mut().find(|(listed_action, _)| listed_action == action) {
+                Some((_, sequences)) => sequences.push(keys),
+                None => actions.push((*action, vec![keys])),
+            }
+        }
+        actions
+    }
+}
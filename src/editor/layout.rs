@@ -0,0 +1,64 @@
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Motion {
+    Down,
+    Up,
+    Left,
+    Right,
+    RowStart,
+    RowEnd,
+    // A screen of rows.
+    PageDown,
+    PageUp,
+}
+
+/* Keys moving the cursor in navigation and selection modes, chosen to sit under the fingers
+of the right hand on the keyboard layout of the user. The arrow keys move the cursor whatever the layout. */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Layout {
+    // t s c r
+    #[default]
+    Bepo,
+    // h j k l
+    Qwerty,
+    // j k l m
+    Azerty,
+    // Only the arrow keys: the letters are left to the other commands.
+    ArrowsOnly,
+}
+
+impl Layout {
+    pub const NAMES: [&'static str; 4] = ["bepo", "qwerty", "azerty", "arrows"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bepo" | "bépo" => Some(Layout::Bepo),
+            "qwerty" => Some(Layout::Qwerty),
+            "azerty" => Some(Layout::Azerty),
+            "arrows" => Some(Layout::ArrowsOnly),
+            _ => None,
+        }
+    }
+    pub fn get_name(&self) -> &str {
+        match self {
+            Layout::Bepo => "bepo",
+            Layout::Qwerty => "qwerty",
+            Layout::Azerty => "azerty",
+            Layout::ArrowsOnly => "arrows",
+        }
+    }
+
+    // The letters moving down, up, left and right, bound by default (see `Keymap`).
+    pub fn get_motion_letters(&self) -> Vec<(char, Motion)> {
+        let letters = match self {
+            Layout::Bepo => ['t', 's', 'c', 'r'],
+            Layout::Qwerty => ['j', 'k', 'h', 'l'],
+            Layout::Azerty => ['k', 'l', 'j', 'm'],
+            Layout::ArrowsOnly => return Vec::new(),
+        };
+        letters
+            .iter()
+            .copied()
+            .zip([Motion::Down, Motion::Up, Motion::Left, Motion::Right])
+            .collect()
+    }
+}
@@ -0,0 +1,180 @@
+use super::swap::state_directory;
+use super::Editor;
+use crate::terminal::event::{parse_notation, Event};
+use crate::text_buffer::write_atomically;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+
+const MACROS_FILE_NAME: &str = "macros";
+// A macro replaying itself would never end.
+const MAX_NESTED_REPLAYS: usize = 100;
+
+/* Keyboard macros: the events read while recording are stored into a register (a-z),
+and replayed as if they were typed again, prompts included. Every event is read through
+`Editor::read_event`, which takes the events to replay before the ones of the terminal.
+The macros are kept into the state directory, one per line: the register, a space, then the keys
+written with `Event::to_notation`. */
+pub struct Macros {
+    recorded: HashMap<char, Vec<Event>>,
+    // Register being recorded into, with the events read since the recording started.
+    recording: Option<(char, Vec<Event>)>,
+    // Events left to replay.
+    replay_queue: VecDeque<Event>,
+    replay_depth: usize,
+    last_replayed: Option<char>,
+}
+
+impl Macros {
+    // constructor
+    pub fn new() -> Self {
+        Self {
+            recorded: HashMap::new(),
+            recording: None,
+            replay_queue: VecDeque::new(),
+            replay_depth: 0,
+            last_replayed: None,
+        }
+    }
+    // The macros recorded during the previous sessions. Lines which can't be read are skipped.
+    pub fn load() -> Self {
+        let mut macros = Self::new();
+        let content = match macros_path().map(std::fs::read_to_string) {
+            Some(Ok(content)) => content,
+            _ => return macros,
+        };
+        for line in content.lines() {
+            let mut chars = line.chars();
+            if let (Some(name), Some(' ')) = (chars.next(), chars.next()) {
+                if let Some(events) = parse_notation(chars.as_str()).filter(|_| name.is_ascii_lowercase()) {
+                    macros.recorded.insert(name, events);
+                }
+            }
+        }
+        macros
+    }
+
+    // accessors
+    pub fn get_recording_name(&self) -> Option<char> {
+        self.recording.as_ref().map(|(name, _)| *name)
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = macros_path().ok_or_else(|| std::io::Error::other("no state directory"))?;
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let mut names: Vec<&char> = self.recorded.keys().collect();
+        names.sort();
+        write_atomically(&path, |writer| {
+            for name in names {
+                let keys: String = self.recorded[name].iter().filter_map(Event::to_notation).collect();
+                writeln!(writer, "{} {}", name, keys)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Default for Macros {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn macros_path() -> Option<PathBuf> {
+    state_directory().map(|directory| directory.join(MACROS_FILE_NAME))
+}
+
+impl Editor {
+    // Next event to process: one left to replay, else one of the terminal, recorded if a macro is being recorded.
+    pub(super) fn read_event(&mut self) -> Event {
+        if let Some(event) = self.macros.replay_queue.pop_front() {
+            return event;
+        }
+        let event = self.terminal.read_event();
+        if let Some((_, events)) = &mut self.macros.recording {
+            if event.get_key().is_some() {
+                events.push(event.clone());
+            }
+        }
+        event
+    }
+
+    // commands
+    // Starts recording into a register, or stops the recording.
+    pub(super) fn toggle_macro_recording(&mut self) {
+        if let Some((name, mut events)) = self.macros.recording.take() {
+            // The keys stopping the recording aren't part of the macro, nor the ones read after them.
+            let stop_keys = self.pending_keys.get_resolved();
+            if let Some(start) = events.windows(stop_keys.len().max(1)).rposition(|keys| keys == stop_keys) {
+                events.truncate(start);
+            }
+            self.status_message = Some(format!("Macro {} recorded ({} keys)", name, events.len()));
+            self.macros.recorded.insert(name, events);
+            if let Err(error) = self.macros.save() {
+                self.status_message = Some(format!("Can't save the macros: {}", error));
+            }
+            return;
+        }
+        let choices: Vec<char> = ('a'..='z').collect();
+        if let Some(name) = self.ask_user_for_char("Record a macro into (a-z): ", &choices) {
+            self.macros.recording = Some((name, Vec::new()));
+        }
+    }
+    // Asks for the macro to replay `count` times.
+    pub(super) fn replay_macro_prompt(&mut self, count: usize) {
+        let choices: Vec<char> = ('a'..='z').chain(std::iter::once('@')).collect();
+        let name = match self.ask_user_for_char("Replay the macro (a-z, @ for the last one): ", &choices) {
+            Some(name) => name,
+            None => return,
+        };
+        let name = if name == '@' {
+            match self.macros.last_replayed {
+                Some(name) => name,
+                None => {
+                    self.status_message = Some(String::from("No macro replayed yet"));
+                    return;
+                }
+            }
+        } else {
+            name
+        };
+        self.replay_macro(name, count);
+    }
+
+    /* Processes the events of the macro `count` times, as one undo step. A macro may replay another one:
+    its events are processed before the rest of the outer macro. */
+    pub(super) fn replay_macro(&mut self, name: char, count: usize) {
+        let events = match self.macros.recorded.get(&name) {
+            Some(events) => events.clone(),
+            None => {
+                self.status_message = Some(format!("No macro recorded into {}", name));
+                return;
+            }
+        };
+        if self.macros.replay_depth >= MAX_NESTED_REPLAYS {
+            self.status_message = Some(String::from("Too many nested macros"));
+            return;
+        }
+        self.macros.last_replayed = Some(name);
+        let outer_queue = std::mem::take(&mut self.macros.replay_queue);
+        self.macros.replay_depth += 1;
+        self.text_buffer.begin_undo_group();
+        for _ in 0..count {
+            self.macros.replay_queue = events.iter().cloned().collect();
+            while !self.macros.replay_queue.is_empty() && !self.will_quit_flag {
+                let event = self.read_event();
+                self.process_event(event);
+            }
+            // A sequence of keys left unfinished doesn't wait for the keys following the macro.
+            self.resolve_pending_keys();
+            if self.will_quit_flag {
+                break;
+            }
+        }
+        self.text_buffer.end_undo_group();
+        self.macros.replay_depth -= 1;
+        self.macros.replay_queue = outer_queue;
+    }
+}
@@ -1,18 +1,46 @@
+use crate::config::Config;
 use crate::terminal::event::{Event, Key};
 use crate::terminal::style::Color;
 use crate::terminal::Terminal;
-use crate::text_buffer::Buffer;
-use crate::text_buffer::BufferStatus;
+use crate::text_buffer::{
+    control_notation, grapheme_width, Buffer, BufferStatus, DiskChange, Position, Range, SaveError, SearchDirection,
+};
+use unicode_segmentation::UnicodeSegmentation;
 use std::cmp::min;
 use std::path::PathBuf;
-use help_menu::HELP_MENU_CONTENT;
+use std::time::Duration;
 use mode::Mode;
+use swap::SwapFile;
+use diff::DiffLine;
+use search::LastSearch;
+use clipboard::ClipboardProvider;
+use macros::Macros;
+use input::PendingKeys;
+use multi_cursor::is_word_char;
+use register::Registers;
+use selection::{Selection, SelectionKind};
 pub use cursor::Cursor;
+pub use keymap::{Action, Keymap, KeymapMode, Resolution};
+pub use layout::{Layout, Motion};
 
 mod help_menu;
 mod cursor;
 mod mode;
 mod input;
+pub(crate) mod swap;
+pub(crate) mod diff;
+mod search;
+pub(crate) mod selection;
+pub(crate) mod register;
+pub(crate) mod clipboard;
+pub(crate) mod multi_cursor;
+mod macros;
+mod layout;
+mod keymap;
+mod mouse;
+
+// Tab widths cycled through by the navigation mode command.
+const TAB_WIDTHS: [usize; 3] = [2, 4, 8];
 
 // This structure represents the text editor.
 pub struct Editor {
@@ -24,27 +52,95 @@ pub struct Editor {
     not the one drawn on the screen ! I didn't create another
     abstraction for the cursor drawn on the screen for simplicity's sake. */
     cursor: Cursor,
+    // Other cursors the edits are done at too (see `multi_cursor`).
+    secondary_cursors: Vec<Position>,
     text_buffer_row_offset: usize,
     text_buffer_col_offset: usize,
     current_mode: Mode,
+    // Message shown into the status bar until the next key press (errors, information).
+    status_message: Option<String>,
+    swap_file: SwapFile,
+    // Swap file left by a previous session, the user is asked what to do with it when the editor runs.
+    stale_swap_file: Option<PathBuf>,
+    // Last change of the file on disk the user was told about, not to tell it again and again.
+    notified_disk_change: Option<DiskChange>,
+    last_search: Option<LastSearch>,
+    // Number of cells between two tab stops.
+    tab_width: usize,
+    // The tab key inserts spaces up to the next tab stop instead of a tab character.
+    expand_tab: bool,
+    // Only during the selection mode.
+    selection: Option<Selection>,
+    registers: Registers,
+    // Register given for the next copy, cut or paste, the unnamed one if None.
+    pending_register: Option<char>,
+    // Count typed before a navigation mode command.
+    pending_count: Option<usize>,
+    // Start of a sequence of keys, waiting for the next key.
+    pending_keys: PendingKeys,
+    // None if no way to reach the clipboard of the system was found.
+    system_clipboard: Option<Box<dyn ClipboardProvider>>,
+    macros: Macros,
+    keymap: Keymap,
+    config: Config,
 }
 
 impl Editor {
     // Constructor.
     /* Returns a new instance of Editor struct,
     with or without argument (which in this case might be a file path, existing or not). */
-    pub fn new(args: Option<String>) -> Self {
-        Self {
+    pub fn new(args: Option<String>, config: Config) -> Self {
+        let mut editor = Self {
             will_quit_flag: false,
             terminal: Terminal::new(),
-            text_buffer: match args {
-                Some(e) => Buffer::new_from_file(PathBuf::from(e)),
-                None => Buffer::new_empty(),
-            },
+            text_buffer: Buffer::new_empty(),
             cursor: Cursor::new(),
+            secondary_cursors: Vec::new(),
             text_buffer_row_offset: 0,
             text_buffer_col_offset: 0,
             current_mode: Mode::new(),
+            status_message: None,
+            swap_file: SwapFile::new(),
+            stale_swap_file: None,
+            notified_disk_change: None,
+            last_search: None,
+            tab_width: config.tab_width,
+            expand_tab: config.expand_tab,
+            selection: None,
+            registers: Registers::new(),
+            pending_register: None,
+            pending_count: None,
+            pending_keys: PendingKeys::new(config.key_timeout),
+            system_clipboard: clipboard::detect(
+                config.clipboard_copy.as_deref(),
+                config.clipboard_paste.as_deref(),
+                |name| std::env::var_os(name),
+            ),
+            macros: Macros::load(),
+            keymap: Keymap::new(config.layout),
+            config,
+        };
+        for (mode, keys, action) in &editor.config.key_bindings {
+            editor.keymap.bind(*mode, keys.clone(), *action);
+        }
+        match args {
+            Some(e) => editor.open_file(PathBuf::from(e)),
+            None => editor.stale_swap_file = swap::find_stale_swap_file(None),
+        }
+        editor
+    }
+
+    // The errors of the configuration file are shown until the first key press.
+    pub fn report_config_errors(&mut self, errors: &[String]) {
+        if let Some(first) = errors.first() {
+            let message = match errors.len() {
+                1 => format!("Config error: {}", first),
+                count => format!("Config error: {} (and {} more)", first, count - 1),
+            };
+            self.status_message = Some(match self.status_message.take() {
+                Some(status_message) => format!("{}. {}", status_message, message),
+                None => message,
+            });
         }
     }
 
@@ -55,215 +151,528 @@ impl Editor {
     fn current_col_position(&self) -> usize {
         self.cursor.get_col()
     }
+    #[cfg(test)]
+    pub(crate) fn get_text_buffer(&self) -> &Buffer {
+        &self.text_buffer
+    }
+    #[cfg(test)]
+    pub(crate) fn get_text_buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.text_buffer
+    }
 
     // cursor
+    /* Moving up or down keeps the column the cursor is drawn at, not its index into the row:
+    rows may mix characters one or two cells wide. */
     fn move_cursor_up(&mut self) {
         if self.current_row_position() > 0 {
-            if self.current_row_position() - self.text_buffer_row_offset == 0 {
-                self.text_buffer_row_offset -= 1;
-            }
-            self.cursor.set_col_row(
-                min(
-                    self.current_col_position(),
-                    self.text_buffer.get_lenght_of_row(self.current_row_position() - 1),
-                ),
-                self.current_row_position() - 1,
-            );
-            if self.current_col_position() < self.terminal.get_size_col() {
-                self.text_buffer_col_offset = 0;
-            } else {
-                self.text_buffer_col_offset =
-                    self.text_buffer.get_lenght_of_row(self.current_row_position()) - self.terminal.get_size_col();
-            }
+            self.move_cursor_to_row(self.current_row_position() - 1);
         }
     }
     fn move_cursor_down(&mut self) {
         if self.current_row_position() + 1 < self.text_buffer.get_lenght() {
-            if self.current_row_position() - self.text_buffer_row_offset + 3 > self.terminal.get_size_row() {
-                self.text_buffer_row_offset += 1;
-            }
-            self.cursor.set_col_row(
-                min(
-                    self.current_col_position(),
-                    self.text_buffer.get_lenght_of_row(self.current_row_position() + 1),
-                ),
-                self.current_row_position() + 1,
-            );
-            if self.current_col_position() < self.terminal.get_size_col() {
-                self.text_buffer_col_offset = 0;
-            } else {
-                self.text_buffer_col_offset =
-                    self.text_buffer.get_lenght_of_row(self.current_row_position()) - self.terminal.get_size_col();
-            }
+            self.move_cursor_to_row(self.current_row_position() + 1);
         }
     }
+    fn move_cursor_to_row(&mut self, row: usize) {
+        let display_col = self
+            .text_buffer
+            .display_col_of(self.current_col_position(), self.current_row_position(), self.tab_width);
+        let col = self.text_buffer.col_at_display_col(display_col, row, self.tab_width);
+        self.move_cursor_at(col, row);
+    }
+    // Left and right step over a whole grapheme cluster (a letter with its accents, an emoji sequence).
     fn move_cursor_left(&mut self) {
-        if self.current_col_position() - self.text_buffer_col_offset > 0 {
-            self.cursor.set_col(self.current_col_position() - 1);
-        } else {
-            if self.text_buffer_col_offset > 0 {
-                self.text_buffer_col_offset -= 1;
-                self.cursor.set_col(self.current_col_position() - 1);
-            }
+        if self.current_col_position() > 0 {
+            let col = self
+                .text_buffer
+                .previous_grapheme_col(self.current_col_position(), self.current_row_position());
+            self.move_cursor_at(col, self.current_row_position());
         }
     }
     fn move_cursor_right(&mut self) {
         if self.current_col_position() < self.text_buffer.get_lenght_of_row(self.current_row_position()) {
-            if self.current_col_position() - self.text_buffer_col_offset + 2 > self.terminal.get_size_col() {
-                self.text_buffer_col_offset += 1;
+            let col = self
+                .text_buffer
+                .next_grapheme_col(self.current_col_position(), self.current_row_position());
+            self.move_cursor_at(col, self.current_row_position());
+        }
+    }
+
+    fn move_cursor(&mut self, motion: Motion) {
+        match motion {
+            Motion::Down => self.move_cursor_down(),
+            Motion::Up => self.move_cursor_up(),
+            Motion::Left => self.move_cursor_left(),
+            Motion::Right => self.move_cursor_right(),
+            Motion::RowStart => self.move_cursor_at(0, self.current_row_position()),
+            Motion::RowEnd => self.move_cursor_at(
+                self.text_buffer.get_lenght_of_row(self.current_row_position()),
+                self.current_row_position(),
+            ),
+            Motion::PageDown => {
+                let row = self.current_row_position() + self.get_page_height();
+                self.move_cursor_to_row(min(row, self.text_buffer.get_lenght() - 1));
+            }
+            Motion::PageUp => {
+                let row = self.current_row_position().saturating_sub(self.get_page_height());
+                self.move_cursor_to_row(row);
             }
-            self.cursor.set_col(self.current_col_position() + 1);
+        }
+    }
+    // Rows are counted from 1, as the user sees them. The row is clamped to the buffer.
+    fn go_to_row(&mut self, row: usize) {
+        let row = row.clamp(1, self.text_buffer.get_lenght().max(1)) - 1;
+        self.move_cursor_at(0, row);
+    }
+    // Rows of text on the screen, above the status bar.
+    fn get_page_height(&self) -> usize {
+        self.terminal.get_last_row().max(1)
+    }
+    fn move_cursor_at(&mut self, col: usize, row: usize) {
+        self.cursor.set_col_row(col, row);
+        self.scroll_to_cursor();
+    }
+    // Position of the cursor on the screen, in terminal cells from the start of its row.
+    fn cursor_display_col(&self) -> usize {
+        self.text_buffer
+            .display_col_of(self.current_col_position(), self.current_row_position(), self.tab_width)
+    }
+    /* Adjusts the offsets so that the cursor is visible.
+    The column offset counts terminal cells, not characters. */
+    fn scroll_to_cursor(&mut self) {
+        let visible_rows = self.terminal.get_last_row().saturating_sub(1);
+        let visible_cols = self.terminal.get_last_col().saturating_sub(1);
+        if self.current_row_position() < self.text_buffer_row_offset {
+            self.text_buffer_row_offset = self.current_row_position();
+        } else if self.current_row_position() - self.text_buffer_row_offset > visible_rows {
+            self.text_buffer_row_offset = self.current_row_position() - visible_rows;
+        }
+        let display_col = self.cursor_display_col();
+        if display_col < self.text_buffer_col_offset {
+            self.text_buffer_col_offset = display_col;
+        } else if display_col - self.text_buffer_col_offset > visible_cols {
+            self.text_buffer_col_offset = display_col - visible_cols;
         }
     }
 
     // display
     fn draw(&mut self) {
         self.terminal.hide_cursor();
-        let mut draw_cursor_row_position = 0;
-        for i in self.text_buffer_row_offset..self.terminal.get_last_row() + self.text_buffer_row_offset {
+        for draw_cursor_row_position in 0..self.terminal.get_last_row() {
+            let i = draw_cursor_row_position + self.text_buffer_row_offset;
+            self.terminal.move_cursor_at(0, draw_cursor_row_position);
+            self.terminal.clear_current_line();
+            // The rows below the end of the buffer are left blank (the buffer may have shrunk).
             if i < self.text_buffer.get_lenght() {
-                self.terminal.move_cursor_at(0, draw_cursor_row_position);
-                draw_cursor_row_position += 1;
-                self.terminal.clear_current_line();
-                for j in self.text_buffer_col_offset..self.terminal.get_last_col() + self.text_buffer_col_offset {
-                    if j < self.text_buffer.get_lenght_of_row(i) {
-                        self.terminal.print(self.text_buffer.borrow_char_at(j, i));
-                    } else {
-                        break;
-                    }
-                }
-            } else {
-                break;
+                self.draw_row(i);
             }
         }
         self.draw_status_bar();
         self.terminal.move_cursor_at(
-            self.current_col_position() - self.text_buffer_col_offset,
+            self.cursor_display_col() - self.text_buffer_col_offset,
             self.current_row_position() - self.text_buffer_row_offset,
         );
         self.terminal.show_cursor();
         self.terminal.flush();
     }
-    fn draw_status_bar(&mut self) {
-        self.terminal.move_cursor_at(0, self.terminal.get_last_row());
-        self.terminal.clear_current_line();
-        let to_print: &str;
-        match self.current_mode {
-            Mode::Navigation => to_print = "Navigation",
-            Mode::Edition => to_print = "Edition",
-            Mode::Selection => to_print = "Selection",
+    /* Prints the visible part of a row, one grapheme cluster at a time,
+    the selected text and the secondary cursors highlighted.
+    Tabs are expanded to spaces, and so is a wide character cut by the left or right edge of the screen.
+    The other control characters are shown in caret notation. */
+    fn draw_row(&mut self, index: usize) {
+        let row = self.text_buffer.borrow_row_at(index).to_string();
+        let selected_cols = self.selected_cols(index);
+        let cursor_cols = self.secondary_cursor_cols(index);
+        let first_cell = self.text_buffer_col_offset;
+        let last_cell = first_cell + self.terminal.get_last_col();
+        // Consecutive graphemes with the same highlighting are printed together.
+        let mut pieces: Vec<(String, bool)> = Vec::new();
+        let mut push_piece = |text: &str, is_selected: bool| match pieces.last_mut() {
+            Some((last_text, last_is_selected)) if *last_is_selected == is_selected => last_text.push_str(text),
+            _ => pieces.push((text.to_string(), is_selected)),
+        };
+        let mut cell = 0;
+        let mut col = 0;
+        for grapheme in row.graphemes(true) {
+            let width = grapheme_width(grapheme, cell, self.tab_width);
+            if cell >= last_cell {
+                break;
+            }
+            let is_selected =
+                selected_cols.is_some_and(|(start, end, _)| start <= col && col < end) || cursor_cols.contains(&col);
+            if cell < first_cell && cell + width <= first_cell {
+                // scrolled out on the left
+            } else if grapheme == "\t" || cell < first_cell || cell + width > last_cell {
+                let hidden = first_cell.saturating_sub(cell);
+                let spaces = " ".repeat(min(cell + width, last_cell) - cell - hidden);
+                push_piece(&spaces, is_selected);
+            } else if let Some(notation) = control_notation(grapheme) {
+                push_piece(&notation, is_selected);
+            } else {
+                push_piece(grapheme, is_selected);
+            }
+            cell += width;
+            col += grapheme.chars().count();
+        }
+        // A selected line break, or a cursor at the end of the row, is shown as a highlighted cell after the row.
+        let line_break_selected = selected_cols.is_some_and(|(_, _, line_break)| line_break);
+        if (line_break_selected || cursor_cols.contains(&col)) && first_cell <= cell && cell < last_cell {
+            push_piece(" ", true);
         }
-        if let Some(path_str) = self.text_buffer.get_path_as_str() {
-            if to_print.len() + path_str.len() < self.terminal.get_size_col() {
-                self.terminal.print_text(to_print, Color::Black, Color::White);
+        for (text, is_selected) in pieces {
+            if is_selected {
+                let style = self.config.colors.selection;
+                self.terminal.print_text(&text, style.foreground, style.background);
+            } else {
+                self.terminal.print(text);
             }
+        }
+    }
+    // The mode, followed by what is pending and the state of the buffer.
+    pub(crate) fn get_status_bar_text(&self) -> String {
+        let mode = match self.current_mode {
+            Mode::Navigation => "Navigation",
+            Mode::Edition => "Edition",
+            Mode::Selection => match &self.selection {
+                Some(selection) => match selection.kind {
+                    SelectionKind::Char => "Selection",
+                    SelectionKind::Line => "Selection line",
+                    SelectionKind::Block => "Selection block",
+                },
+                None => "Selection",
+            },
+        };
+        let file_format = if self.config.show_file_format {
+            format!(
+                " {}:{} {}{}",
+                if self.expand_tab { "spaces" } else { "tabs" },
+                self.tab_width,
+                self.text_buffer.get_line_ending().get_name(),
+                if self.text_buffer.has_final_newline() { "" } else { " noeol" },
+            )
+        } else {
+            String::new()
+        };
+        let position = if self.config.show_position {
+            format!(" {}:{}", self.current_row_position() + 1, self.current_col_position() + 1)
         } else {
-            if to_print.len() + "unsaved".len() - 1 < self.terminal.get_size_col() {
-                self.terminal.print_text(to_print, Color::Black, Color::White);
+            String::new()
+        };
+        format!(
+            "{}{}{}{}{}{}{}{}{}",
+            mode,
+            self.macros.get_recording_name().map_or(String::new(), |name| format!(" recording {}", name)),
+            if self.has_secondary_cursors() {
+                format!(" {} cursors", self.secondary_cursors.len() + 1)
+            } else {
+                String::new()
+            },
+            self.pending_register.map_or(String::new(), |name| format!(" \"{}", name)),
+            self.pending_count.map_or(String::new(), |count| format!(" {}", count)),
+            if self.pending_keys.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", self.pending_keys.get_notation())
+            },
+            position,
+            file_format,
+            if self.text_buffer.is_read_only() { " read-only" } else { "" }
+        )
+    }
+    fn draw_status_bar(&mut self) {
+        self.terminal.move_cursor_at(0, self.terminal.get_last_row());
+        self.terminal.clear_current_line();
+        let to_print = self.get_status_bar_text();
+        let path_len = self.text_buffer.get_path_as_str().map_or("unsaved".len(), |s| s.len());
+        if to_print.len() + path_len < self.terminal.get_size_col() {
+            let style = self.config.colors.status_bar;
+            self.terminal.print_text(&to_print, style.foreground, style.background);
+            // The message takes the room left between the mode and the path.
+            if let Some(message) = &self.status_message {
+                let room = self.terminal.get_size_col() - to_print.len() - path_len;
+                if room > 2 {
+                    let message: String = message.chars().take(room - 2).collect();
+                    self.terminal.print(format!(" {}", message));
+                }
             }
         }
-        
+
+
         match self.text_buffer.get_path_as_str() {
             Some(s) => {
                 if s.len() < self.terminal.get_size_col() {
                     self.terminal
                         .move_cursor_at(self.terminal.get_size_col() - s.len(), self.terminal.get_last_row());
-                    let (fg_color, bg_color) = match self.text_buffer.get_status() {
-                        BufferStatus::Saved => (Color::White, Color::Green),
-                        BufferStatus::Unsaved => (Color::White, Color::Red),
+                    let style = match self.text_buffer.get_status() {
+                        BufferStatus::Saved => self.config.colors.saved,
+                        BufferStatus::Unsaved => self.config.colors.unsaved,
                     };
-                    self.terminal.print_text(s, fg_color, bg_color);
+                    self.terminal.print_text(s, style.foreground, style.background);
                 }
             }
             None => {
                 if self.terminal.get_size_col() > 6 {
                     self.terminal.move_cursor_at(self.terminal.get_size_col() - 7, self.terminal.get_last_row());
-                    self.terminal.print_text("unsaved", Color::White, Color::Red);
+                    let style = self.config.colors.unsaved;
+                    self.terminal.print_text("unsaved", style.foreground, style.background);
                 }
             }
         }
     }
     fn switch_mode_to_navigation(&mut self) {
+        // What was typed during the edition mode session is undone in one step.
+        self.text_buffer.commit_transaction();
+        self.selection = None;
         self.current_mode.switch_to_navigation();
     }
     fn switch_mode_to_edition(&mut self) {
+        if self.text_buffer.is_read_only() {
+            self.status_message = Some(String::from("The buffer is read-only"));
+            return;
+        }
+        self.text_buffer.commit_transaction();
         self.current_mode.switch_to_edition();
     }
+    // Tells the user that the buffer can't be modified, returns true if it can't.
+    fn refuse_read_only(&mut self) -> bool {
+        if self.text_buffer.is_read_only() {
+            self.status_message = Some(String::from("The buffer is read-only"));
+        }
+        self.text_buffer.is_read_only()
+    }
+    // A selection is made with the primary cursor only.
     fn switch_mode_to_selection(&mut self) {
+        self.clear_secondary_cursors();
         self.current_mode.switch_to_selection();
     }
     // events
-    fn navigation_mode_key_process(&mut self, key: Key) {
-        match key {
-            Key::Char('e') => self.switch_mode_to_edition(),
-            // arrow keys
-            Key::Char('t') => self.move_cursor_down(),
-            Key::Char('s') => self.move_cursor_up(),
-            Key::Char('c') => self.move_cursor_left(),
-            Key::Char('r') => self.move_cursor_right(),
-            _ => (),
+    fn get_keymap_mode(&self) -> KeymapMode {
+        match self.current_mode {
+            Mode::Navigation => KeymapMode::Navigation,
+            Mode::Edition => KeymapMode::Edition,
+            Mode::Selection => KeymapMode::Selection,
         }
     }
-    fn edition_mode_key_process(&mut self, key: Key) {
-        match key {
-            Key::Char(c) => {
-                self.text_buffer.insert_char(
-                    self.current_col_position(),
-                    self.current_row_position(),
-                    c);
-                self.move_cursor_right(); // double vérif de current col pos, à revoir
-            },
-            Key::Backspace => self.backspace_key_pressed(),
-            Key::Enter => self.enter_key_pressed(),
-            Key::Esc => self.switch_mode_to_navigation(),
-            _ => (),
+    /* A count typed before an action repeats it, or is given to it: the row commands take that many rows,
+    a macro is replayed that many times. The repeated edits are undone in one step,
+    and repeating stops when it doesn't change anything. */
+    fn run_action_with_count(&mut self, action: Action) {
+        match self.pending_count.take() {
+            None => self.run_action(action, None),
+            Some(count) if !action.is_repeatable() => self.run_action(action, Some(count)),
+            Some(count) => self.repeat_action(action, count),
+        }
+        // Outside of the edition mode, every action is an undo step of its own.
+        if !self.current_mode.is_edition_mode() {
+            self.text_buffer.commit_transaction();
         }
     }
-    fn selection_mode_key_process(&mut self, key: Key) {}
-    fn key_pressed(&mut self, key: Key) {
-        match self.current_mode {
-            Mode::Navigation => self.navigation_mode_key_process(key),
-            Mode::Edition => self.edition_mode_key_process(key),
-            Mode::Selection => self.selection_mode_key_process(key),
-        }
-    }
-    fn key_pressed_with_ctrl(&mut self, key: Key) {
-        match key {
-            Key::Char('c') => self.will_quit_flag = true,
-            Key::Char('s') => match self.text_buffer.get_path() {
-                Some(_) => match self.text_buffer.save() {
-                    BufferStatus::Saved => {}
-                    BufferStatus::Unsaved => {}
-                },
-                None => {
-                    let path = self.ask_user_for_path();
-                    if let Some(p) = path {
-                        match self.text_buffer.save_as(p) {
-                            BufferStatus::Saved => {}
-                            BufferStatus::Unsaved => {}
-                        }
-                    }
-                }
-            },
-            Key::Char('o') => {
-                self.open_new_file();
+    fn repeat_action(&mut self, action: Action, count: usize) {
+        self.text_buffer.begin_undo_group();
+        for _ in 0..count {
+            let before = self.repeat_state();
+            self.run_action(action, None);
+            if self.repeat_state() == before {
+                break;
             }
-            Key::Char('h') => self.open_help_menu(), // for some reasons ctrl + backspace opens also the menu
-            _ => (),
         }
+        self.text_buffer.end_undo_group();
     }
-    fn backspace_key_pressed(&mut self) {
-        if self.current_col_position() > 0 {
+    // What a repeated action may change: the cursors and the text.
+    fn repeat_state(&self) -> (Position, Vec<Position>, usize) {
+        (self.primary_cursor(), self.secondary_cursors.clone(), self.text_buffer.get_version())
+    }
+    // The count is given to the actions which aren't repeated.
+    fn run_action(&mut self, action: Action, count: Option<usize>) {
+        match action {
+            // The cursor moves every cursor.
+            Action::Move(motion) => self.at_every_cursor(|editor| editor.move_cursor(motion)),
+            // modes
+            Action::EditionMode => self.switch_mode_to_edition(),
+            Action::NavigationMode => self.switch_mode_to_navigation(),
+            Action::SelectChars => self.select(SelectionKind::Char),
+            Action::SelectLines => self.select(SelectionKind::Line),
+            Action::SelectBlock => self.select(SelectionKind::Block),
+            // files
+            Action::Quit => self.will_quit_flag = true,
+            Action::Save => self.save(),
+            Action::OpenFile => self.open_new_file(),
+            Action::ToggleHelp => self.open_help_menu(),
+            // history
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            // rows
+            Action::GoToFirstRow => self.go_to_row(count.unwrap_or(1)),
+            Action::GoToLastRow => self.go_to_row(count.unwrap_or(self.text_buffer.get_lenght())),
+            // registers
+            Action::ChooseRegister => self.choose_register(),
+            Action::PasteAfter => self.paste(true),
+            Action::PasteBefore => self.paste(false),
+            Action::PasteAtCursors => self.edit_at_every_cursor(Self::paste_at_cursor),
+            Action::CopyRows => self.copy_rows(count.unwrap_or(1)),
+            Action::CutRows => self.cut_rows(count.unwrap_or(1)),
+            // macros
+            Action::RecordMacro => self.toggle_macro_recording(),
+            Action::ReplayMacro => self.replay_macro_prompt(count.unwrap_or(1)),
+            // multiple cursors
+            Action::AddCursorBelow => self.add_cursor_vertically(true),
+            Action::AddCursorAbove => self.add_cursor_vertically(false),
+            Action::AddCursorAtNextOccurrence => self.add_cursor_at_next_occurrence(),
+            Action::AddCursorsAtMatches => self.add_cursors_at_matches(),
+            Action::ClearCursors => self.clear_secondary_cursors(),
+            // search
+            Action::SearchForward => {
+                self.search_prompt(SearchDirection::Forward);
+            }
+            Action::SearchBackward => {
+                self.search_prompt(SearchDirection::Backward);
+            }
+            Action::SearchNext => self.search_next(false),
+            Action::SearchPrevious => self.search_next(true),
+            Action::Replace => self.replace_prompt(),
+            // file format
+            Action::ToggleLineEnding => self
+                .text_buffer
+                .set_line_ending(self.text_buffer.get_line_ending().toggled()),
+            Action::ToggleFinalNewline => self
+                .text_buffer
+                .set_final_newline(!self.text_buffer.has_final_newline()),
+            // indentation
+            Action::ToggleExpandTab => self.expand_tab = !self.expand_tab,
+            Action::CycleTabWidth => {
+                self.tab_width = TAB_WIDTHS
+                    .iter()
+                    .copied()
+                    .find(|width| *width > self.tab_width)
+                    .unwrap_or(TAB_WIDTHS[0]);
+                self.scroll_to_cursor();
+            }
+            // edition
+            Action::InsertTab => self.edit_at_every_cursor(Self::tab_key_pressed),
+            Action::DeleteBackward => self.edit_at_every_cursor(Self::backspace_key_pressed),
+            Action::DeleteForward => self.edit_at_every_cursor(Self::delete_key_pressed),
+            Action::Unindent => self.edit_at_every_cursor(Self::back_tab_key_pressed),
+            Action::NewLine => self.edit_at_every_cursor(Self::enter_key_pressed),
+            Action::DeleteWord => self.edit_at_every_cursor(Self::delete_word),
+            // selection
+            Action::CopySelection => self.copy_selection(),
+            Action::CutSelection => self.cut_selection(),
+            Action::DeleteSelection => self.delete_selection(),
+            Action::ReplaceSelection => self.replace_selection(),
+            Action::PasteOverSelection => self.paste_over_selection(),
+        }
+    }
+    // The edition actions may be bound in any mode.
+    fn edit_at_every_cursor(&mut self, action: impl Fn(&mut Self)) {
+        if !self.refuse_read_only() {
+            self.at_every_cursor(action);
+        }
+    }
+    fn tab_key_pressed(&mut self) {
+        if self.expand_tab {
+            let spaces = self.tab_width - self.cursor_display_col() % self.tab_width;
+            for _ in 0..spaces {
+                self.text_buffer
+                    .insert_char(self.current_col_position(), self.current_row_position(), ' ');
+                self.move_cursor_right();
+            }
+        } else {
             self.text_buffer
-                .delete_char(self.current_col_position() - 1, self.current_row_position());
-            self.move_cursor_left();
+                .insert_char(self.current_col_position(), self.current_row_position(), '\t');
+            self.move_cursor_right();
+        }
+    }
+    fn backspace_key_pressed(&mut self) {
+        if self.expand_tab && self.current_col_position() > 0 && self.is_in_soft_tab_indentation() {
+            // A whole indentation level made of spaces goes at once, back to the previous tab stop.
+            let col = (self.current_col_position() - 1) / self.tab_width * self.tab_width;
+            self.delete_before_cursor(col);
+        } else if self.current_col_position() > 0 {
+            // The whole grapheme cluster goes, not only its last accent.
+            let col = self
+                .text_buffer
+                .previous_grapheme_col(self.current_col_position(), self.current_row_position());
+            self.delete_before_cursor(col);
         } else if self.current_row_position() != 0 {
             let previous_len_row_above = self.text_buffer.get_lenght_of_row(self.current_row_position() - 1);
             let mut data = self.text_buffer.remove_row_to_get_data(self.current_row_position());
             self.text_buffer.push_vec_to_row(self.current_row_position() - 1, &mut data);
-            self.cursor.set_col_row(previous_len_row_above, self.current_row_position() - 1)
+            self.move_cursor_at(previous_len_row_above, self.current_row_position() - 1);
+        }
+    }
+    // Deletes the grapheme after the cursor, or joins the next row at the end of a row.
+    fn delete_key_pressed(&mut self) {
+        let (col, row) = (self.current_col_position(), self.current_row_position());
+        let end = if col < self.text_buffer.get_lenght_of_row(row) {
+            Position::new(self.text_buffer.next_grapheme_col(col, row), row)
+        } else if row + 1 < self.text_buffer.get_lenght() {
+            Position::new(0, row + 1)
+        } else {
+            return;
+        };
+        self.text_buffer.delete_range(Range::new(Position::new(col, row), end));
+    }
+    /* Removes a tab from the start of the row, or the spaces back to the previous tab stop.
+    The cursor stays on the same char. */
+    fn back_tab_key_pressed(&mut self) {
+        let row = self.current_row_position();
+        let spaces = self.text_buffer.borrow_row_at(row).chars().take_while(|c| *c == ' ').count();
+        let removed = if spaces == 0 && self.text_buffer.borrow_row_at(row).chars().next() == Some('\t') {
+            1
+        } else if spaces % self.tab_width == 0 {
+            min(spaces, self.tab_width)
+        } else {
+            spaces % self.tab_width
+        };
+        if removed > 0 {
+            self.text_buffer
+                .delete_range(Range::new(Position::new(0, row), Position::new(removed, row)));
+            self.move_cursor_at(self.current_col_position().saturating_sub(removed), row);
         }
     }
+    // Deletes the text of the cursor's row from `col` to the cursor, which moves to `col`.
+    fn delete_before_cursor(&mut self, col: usize) {
+        let row = self.current_row_position();
+        self.text_buffer
+            .delete_range(Range::new(Position::new(col, row), Position::new(self.current_col_position(), row)));
+        self.move_cursor_at(col, row);
+    }
+    /* Deletes from the cursor to the start of the next word on its row: the rest of the word, or of the symbols,
+    then the spaces following it. */
+    fn delete_word(&mut self) {
+        let row = self.current_row_position();
+        let chars: Vec<char> = self.text_buffer.borrow_row_at(row).chars().collect();
+        let start = self.current_col_position();
+        let mut end = start;
+        match chars.get(start) {
+            Some(c) if is_word_char(*c) => {
+                while end < chars.len() && is_word_char(chars[end]) {
+                    end += 1;
+                }
+            }
+            Some(c) if !c.is_whitespace() => {
+                while end < chars.len() && !is_word_char(chars[end]) && !chars[end].is_whitespace() {
+                    end += 1;
+                }
+            }
+            _ => (),
+        }
+        while end < chars.len() && chars[end].is_whitespace() {
+            end += 1;
+        }
+        if end > start {
+            self.text_buffer
+                .delete_range(Range::new(Position::new(start, row), Position::new(end, row)));
+        }
+    }
+    // True if there are only spaces before the cursor on its row.
+    fn is_in_soft_tab_indentation(&self) -> bool {
+        self.text_buffer
+            .borrow_row_at(self.current_row_position())
+            .chars()
+            .take(self.current_col_position())
+            .all(|c| c == ' ')
+    }
     fn enter_key_pressed(&mut self) {
         if self.current_col_position() == self.text_buffer.get_lenght_of_row(self.current_row_position()) {
             self.text_buffer.insert_row_at(self.current_row_position() + 1);
@@ -273,45 +682,353 @@ impl Editor {
                 .text_buffer
                 .remove_row_from(self.current_col_position(), self.current_row_position());
             self.text_buffer.insert_row_at_with_vec(self.current_row_position() + 1, vec);
-            self.move_cursor_down();
-            self.cursor.set_col(0);
+            self.move_cursor_at(0, self.current_row_position() + 1);
+        }
+    }
+    // The buffer is only trimmed once it's known to be written: not when it failed to load, nor without a path.
+    fn save(&mut self) {
+        if self.text_buffer.is_read_only() {
+            self.status_message = Some(format!("Can't save: {}", SaveError::ReadOnly));
+            return;
+        }
+        let new_path = match self.text_buffer.get_path() {
+            Some(_) => None,
+            None => match self.ask_user_for_path() {
+                Some(path) => Some(path),
+                None => return,
+            },
+        };
+        if self.config.trim_trailing_whitespace {
+            self.trim_trailing_whitespace();
+        }
+        let result = match new_path {
+            Some(path) => self.text_buffer.save_as(path),
+            None => self.text_buffer.save(),
+        };
+        match result {
+            Ok(_) => self.file_saved(),
+            Err(SaveError::ModifiedOnDisk(change)) => self.ask_how_to_save_over(change),
+            Err(error) => self.status_message = Some(format!("Can't save: {}", error)),
+        }
+    }
+    // In one undo step. The cursor stays where it was, or at the end of its row if it was in the removed spaces.
+    fn trim_trailing_whitespace(&mut self) {
+        self.text_buffer.commit_transaction();
+        for row in 0..self.text_buffer.get_lenght() {
+            let lenght = self.text_buffer.get_lenght_of_row(row);
+            let trimmed_lenght = self
+                .text_buffer
+                .borrow_row_at(row)
+                .to_string()
+                .trim_end_matches([' ', '\t'])
+                .chars()
+                .count();
+            if trimmed_lenght < lenght {
+                self.text_buffer
+                    .delete_range(Range::new(Position::new(trimmed_lenght, row), Position::new(lenght, row)));
+            }
+        }
+        self.text_buffer.commit_transaction();
+        let cursor = self.text_buffer.clamp_position(self.primary_cursor());
+        self.move_cursor_at(cursor.col, cursor.row);
+    }
+    fn file_saved(&mut self) {
+        self.swap_file.remove();
+        self.notified_disk_change = None;
+    }
+    // The file changed on disk since it was loaded: the user decides whose changes are kept.
+    fn ask_how_to_save_over(&mut self, change: DiskChange) {
+        loop {
+            let choice = match change {
+                DiskChange::Modified(_) => self.ask_user_for_char(
+                    "The file was modified by another program: (o)verwrite, (r)eload, (d)iff, (c)ancel ",
+                    &['o', 'r', 'd', 'c'],
+                ),
+                DiskChange::Deleted => self.ask_user_for_char(
+                    "The file was deleted by another program: (o)verwrite, (c)ancel ",
+                    &['o', 'c'],
+                ),
+            };
+            match choice {
+                Some('o') => {
+                    match self.text_buffer.save_overwriting() {
+                        Ok(_) => self.file_saved(),
+                        Err(error) => self.status_message = Some(format!("Can't save: {}", error)),
+                    }
+                    break;
+                }
+                Some('r') => {
+                    self.reload_file();
+                    break;
+                }
+                Some('d') => self.show_disk_diff(),
+                _ => break,
+            }
+        }
+    }
+    fn reload_file(&mut self) {
+        self.clear_secondary_cursors();
+        match self.text_buffer.reload() {
+            Ok(_) => {
+                self.notified_disk_change = None;
+                self.swap_file.remove();
+                let row = min(self.current_row_position(), self.text_buffer.get_lenght() - 1);
+                let col = min(self.current_col_position(), self.text_buffer.get_lenght_of_row(row));
+                self.move_cursor_at(col, row);
+                self.status_message = Some(String::from("Reloaded (undo to get your edits back)"));
+            }
+            Err(error) => self.status_message = Some(format!("Can't reload: {}", error)),
+        }
+    }
+    /* Called regularly: a file modified by another program is reloaded if the buffer has no edit
+    (after asking), otherwise the user is warned that saving will need a decision. */
+    fn check_file_on_disk(&mut self) {
+        let change = match self.text_buffer.check_disk() {
+            Some(change) => change,
+            None => return,
+        };
+        if self.notified_disk_change.as_ref() == Some(&change) {
+            return;
+        }
+        self.notified_disk_change = Some(change.clone());
+        match change {
+            DiskChange::Modified(_) if !self.text_buffer.has_unsaved_changes() => {
+                self.draw();
+                if let Some('r') = self.ask_user_for_char(
+                    "The file was modified by another program: (r)eload, (k)eep this version ",
+                    &['r', 'k'],
+                ) {
+                    self.reload_file();
+                }
+            }
+            DiskChange::Modified(_) => {
+                self.status_message = Some(String::from("The file was modified by another program"));
+            }
+            DiskChange::Deleted => {
+                self.status_message = Some(String::from("The file was deleted by another program"));
+            }
+        }
+    }
+    fn show_disk_diff(&mut self) {
+        let on_disk = match self.text_buffer.get_path() {
+            Some(path) => String::from_utf8_lossy(&std::fs::read(path).unwrap_or_default()).into_owned(),
+            None => String::new(),
+        };
+        let mut current_text = Vec::new();
+        let _ = self.text_buffer.write_to(&mut current_text);
+        self.show_diff(&on_disk, &String::from_utf8_lossy(&current_text));
+    }
+    fn undo(&mut self) {
+        self.clear_secondary_cursors();
+        if let Some((col, row)) = self.text_buffer.undo() {
+            self.move_cursor_at(col, row);
+        }
+    }
+    fn redo(&mut self) {
+        self.clear_secondary_cursors();
+        if let Some((col, row)) = self.text_buffer.redo() {
+            self.move_cursor_at(col, row);
         }
     }
     fn open_new_file(&mut self) {
         if let Some(path) = self.ask_user_for_path() {
-            self.text_buffer = Buffer::new_from_file(path);
+            self.open_file(path);
         }
     }
-    fn open_help_menu(&mut self) {
+    /* A file which can't be read is opened as an empty read-only buffer,
+    so that it can't be overwritten by mistake. */
+    fn open_file(&mut self, path: PathBuf) {
+        self.swap_file.remove();
+        self.notified_disk_change = None;
+        self.clear_secondary_cursors();
+        self.text_buffer = match Buffer::new_from_file(path.clone()) {
+            Ok(buffer) => {
+                self.stale_swap_file = swap::find_stale_swap_file(Some(&path));
+                buffer
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Can't open {}: {}", path.display(), error));
+                Buffer::new_read_only(path)
+            }
+        };
+        self.cursor.set_col_row(0, 0);
+        self.text_buffer_row_offset = 0;
+        self.text_buffer_col_offset = 0;
+    }
+    // Offers to recover the edits stored into a swap file left by a crashed session.
+    fn offer_recovery(&mut self, swap_path: PathBuf) {
+        let (file_path, text) = match swap::read_swap_file(&swap_path) {
+            Ok(content) => (content.file_path, content.text),
+            Err(_) => return,
+        };
+        let mut current_text = Vec::new();
+        let _ = self.text_buffer.write_to(&mut current_text);
+        if current_text == text.as_bytes() {
+            let _ = std::fs::remove_file(&swap_path);
+            return;
+        }
+        loop {
+            let choice = self.ask_user_for_char(
+                &match &file_path {
+                    Some(file_path) => format!(
+                        "Unsaved edits of {} found in {}: (r)ecover, (d)iff, (x) discard ",
+                        file_path.display(),
+                        swap_path.display()
+                    ),
+                    None => format!("Unsaved edits found in {}: (r)ecover, (d)iff, (x) discard ", swap_path.display()),
+                },
+                &['r', 'd', 'x'],
+            );
+            match choice {
+                Some('r') => {
+                    // The file on disk is still the one the buffer was loaded from.
+                    self.text_buffer.replace_text(&text);
+                    // An unnamed buffer gets the file the edits were made to, if they were.
+                    if let (None, Some(file_path)) = (self.text_buffer.get_path(), file_path.clone()) {
+                        self.text_buffer.set_path(file_path);
+                    }
+                    let _ = std::fs::remove_file(&swap_path);
+                    break;
+                }
+                Some('d') => {
+                    let current_text = String::from_utf8_lossy(&current_text).into_owned();
+                    self.show_diff(&current_text, &text);
+                }
+                Some('x') => {
+                    let _ = std::fs::remove_file(&swap_path);
+                    break;
+                }
+                _ => {
+                    self.status_message = Some(format!("Swap file kept: {}", swap_path.display()));
+                    break;
+                }
+            }
+        }
+    }
+    // Shows what changed from `old` to `new`, with a few lines of context around the changes.
+    fn show_diff(&mut self, old: &str, new: &str) {
+        const CONTEXT: usize = 2;
+        let diff = diff::diff_lines(old, new);
+        let is_change = |line: &DiffLine| !matches!(line, DiffLine::Same(_));
+        let mut lines = Vec::new();
+        for (i, line) in diff.iter().enumerate() {
+            let near_change = diff[i.saturating_sub(CONTEXT)..min(i + CONTEXT + 1, diff.len())]
+                .iter()
+                .any(is_change);
+            if !near_change {
+                if lines.last().is_some_and(|(text, _): &(String, Color)| text != "...") {
+                    lines.push((String::from("..."), Color::Grey));
+                }
+                continue;
+            }
+            lines.push(match line {
+                DiffLine::Same(text) => (format!("  {}", text), Color::White),
+                DiffLine::Removed(text) => (format!("- {}", text), Color::Red),
+                DiffLine::Added(text) => (format!("+ {}", text), Color::Green),
+            });
+        }
+        self.show_text_view("Diff (- on disk, + unsaved edits)", lines);
+    }
+    // Full screen view of colored lines, scrolled with up/down arrows or the wheel, left with escape or the help key.
+    fn show_text_view(&mut self, title: &str, lines: Vec<(String, Color)>) {
+        let mut first_line = 0;
+        loop {
+            self.terminal.clear_all();
+            self.terminal.hide_cursor();
+            self.terminal.move_cursor_at(0, 0);
+            self.terminal.print_text(title, Color::Black, Color::White);
+            let visible_rows = self.terminal.get_size_row().saturating_sub(2);
+            for (row, (text, color)) in lines.iter().skip(first_line).take(visible_rows).enumerate() {
+                self.terminal.move_cursor_at(0, row + 2);
+                let text: String = text.chars().take(self.terminal.get_size_col()).collect();
+                self.terminal.print_text(&text, *color, Color::Black);
+            }
+            self.terminal.flush();
+            match self.read_event() {
+                Event::KeyPressed(Key::DownArrow) if first_line + visible_rows < lines.len() => first_line += 1,
+                Event::KeyPressed(Key::UpArrow) if first_line > 0 => first_line -= 1,
+                Event::MouseScrolledDown => {
+                    first_line = min(first_line + mouse::SCROLL_ROWS, lines.len().saturating_sub(visible_rows));
+                }
+                Event::MouseScrolledUp => first_line = first_line.saturating_sub(mouse::SCROLL_ROWS),
+                Event::KeyPressed(Key::Esc) | Event::KeyPressed(Key::Char('q')) => break,
+                Event::CtrlKeyPressed(Key::Char('c')) => break,
+                event => {
+                    if self.keymap.get(KeymapMode::Global, &[event]) == Some(Action::ToggleHelp) {
+                        break;
+                    }
+                }
+            }
+        }
         self.terminal.clear_all();
-        self.terminal.hide_cursor();
+        self.terminal.flush();
+    }
 
-        self.terminal.move_cursor_at(0, 0);
-        self.terminal.print_text("Help menu\n\n\r", Color::Black, Color::White);
-        self.terminal.print(HELP_MENU_CONTENT);
+    fn open_help_menu(&mut self) {
+        let lines = help_menu::help_menu_lines(&self.keymap);
+        self.show_text_view("Help menu (up/down arrows to scroll, Esc to close)", lines);
+    }
 
+    fn print_prompt(&mut self, text: &str) {
+        let style = self.config.colors.prompt;
+        self.terminal.print_text(text, style.foreground, style.background);
+    }
+    // Asks a question into the status bar until one of `choices` is typed. Escape gives None.
+    fn ask_user_for_char(&mut self, question: &str, choices: &[char]) -> Option<char> {
+        self.terminal.move_cursor_at(0, self.terminal.get_last_row() - 1);
+        self.terminal.clear_current_line();
+        let question: String = question.chars().take(self.terminal.get_size_col()).collect();
+        self.print_prompt(&question);
         self.terminal.flush();
+        let choice = loop {
+            match self.read_event() {
+                Event::KeyPressed(Key::Char(c)) if choices.contains(&c) => break Some(c),
+                Event::KeyPressed(Key::Esc) | Event::CtrlKeyPressed(Key::Char('c')) => break None,
+                _ => (),
+            }
+        };
+        self.terminal.move_cursor_at(0, self.terminal.get_last_row() - 1);
+        self.terminal.clear_current_line();
+        choice
+    }
+
+    // Asks for a line of text into the status bar. Escape gives None.
+    fn ask_user_for_text(&mut self, question: &str) -> Option<String> {
+        let mut text = String::new();
         loop {
-            match self.terminal.read_event() {
-                Event::CtrlKeyPressed(key) => match key {
-                    Key::Char(c) if c == 'c' || c == 'h' => break,
-                    _ => (),
-                },
+            self.terminal.move_cursor_at(0, self.terminal.get_last_row() - 1);
+            self.terminal.clear_current_line();
+            let prompt: String = format!("{}{}", question, text)
+                .chars()
+                .take(self.terminal.get_size_col())
+                .collect();
+            self.print_prompt(&prompt);
+            self.terminal.flush();
+            match self.read_event() {
+                Event::KeyPressed(Key::Char(c)) => text.push(c),
+                Event::KeyPressed(Key::Backspace) => {
+                    text.pop();
+                }
+                Event::KeyPressed(Key::Enter) => break,
+                Event::KeyPressed(Key::Esc) | Event::CtrlKeyPressed(Key::Char('c')) => {
+                    self.terminal.clear_current_line();
+                    return None;
+                }
                 _ => (),
             }
         }
-        self.terminal.clear_all();
-        self.terminal.flush();
+        self.terminal.clear_current_line();
+        Some(text)
     }
 
     fn ask_user_for_path(&mut self) -> Option<PathBuf> {
         self.terminal.move_cursor_at(0, self.terminal.get_last_row() - 1);
         self.terminal.clear_current_line();
-        self.terminal.print_text("Path: ", Color::White, Color::Blue);
+        self.print_prompt("Path: ");
         self.terminal.flush();
         let mut path_buffer = String::new();
         loop {
-            match self.terminal.read_event() {
+            match self.read_event() {
                 Event::KeyPressed(key) => match key {
                     Key::Char(c)
                         if path_buffer.len() + 7 < self.terminal.get_size_col()
@@ -325,7 +1042,7 @@ impl Editor {
                     {
                         path_buffer.push(c);
                     }
-                    Key::Backspace if path_buffer.len() > 0 => {
+                    Key::Backspace if !path_buffer.is_empty() => {
                         path_buffer.pop();
                     }
                     Key::Enter => break,
@@ -335,20 +1052,16 @@ impl Editor {
                     }
                     _ => (),
                 },
-                Event::CtrlKeyPressed(key) => match key {
-                    Key::Char(c) if c == 'c' => {
-                        path_buffer.clear();
-                        break;
-                    }
-                    _ => (),
-                },
+                Event::CtrlKeyPressed(Key::Char('c')) => {
+                    path_buffer.clear();
+                    break;
+                }
                 _ => (),
             }
             self.terminal.move_cursor_at(0, self.terminal.get_size_row() - 2);
             self.terminal.clear_current_line();
 
-            self.terminal.print_text("Path: ", Color::White, Color::Blue);
-            self.terminal.print_text(&path_buffer, Color::White, Color::Blue);
+            self.print_prompt(&format!("Path: {}", path_buffer));
 
             self.terminal.flush();
         }
@@ -364,19 +1077,63 @@ impl Editor {
     pub fn run(&mut self) {
         self.terminal.enter_alternate_screen();
         self.terminal.enable_raw_mode();
+        self.terminal.enable_mouse_capture();
         loop {
+            if let Some(swap_path) = self.stale_swap_file.take() {
+                self.draw();
+                self.offer_recovery(swap_path);
+            }
             self.draw();
-            if self.will_quit_flag == true {
+            if self.will_quit_flag {
                 break;
             }
-            match self.terminal.read_event() {
-                Event::KeyPressed(key) => {
-                    self.key_pressed(key);
-                }
-                Event::CtrlKeyPressed(key) => {
-                    self.key_pressed_with_ctrl(key);
+            /* The swap file is written when typing stops for a while, and regularly during long typing sessions.
+            The file on disk is checked for changes made by other programs when the user is idle.
+            A sequence of keys waits for its next key until the timeout. */
+            if let Some(time_left) = self.pending_keys.get_time_left() {
+                if !self.terminal.poll_event(min(time_left, swap::SWAP_WRITE_DELAY)) {
+                    if self.pending_keys.get_time_left() == Some(Duration::ZERO) {
+                        self.resolve_pending_keys();
+                    }
+                    continue;
                 }
-                _ => (),
+            } else if !self.terminal.poll_event(swap::SWAP_WRITE_DELAY) {
+                self.write_swap_file();
+                self.check_file_on_disk();
+                continue;
+            }
+            if self.swap_file.is_write_delay_elapsed() {
+                self.write_swap_file();
+            }
+            let event = self.read_event();
+            self.process_event(event);
+        }
+        // A clean quit: the edits are deliberately left behind.
+        self.swap_file.remove();
+    }
+    pub(crate) fn process_event(&mut self, event: Event) {
+        match event {
+            event if event.get_key().is_some() => {
+                self.status_message = None;
+                self.key_pressed(event);
+            }
+            Event::MouseClicked(col, row) => {
+                self.status_message = None;
+                self.mouse_clicked(col, row);
+            }
+            Event::MouseDragged(col, row) => self.mouse_dragged(col, row),
+            Event::MouseScrolledUp => self.mouse_scrolled(false),
+            Event::MouseScrolledDown => self.mouse_scrolled(true),
+            _ => (),
+        }
+    }
+    fn write_swap_file(&mut self) {
+        if !self.config.swap_file {
+            return;
+        }
+        if self.swap_file.is_outdated(&self.text_buffer) && !self.text_buffer.is_read_only() {
+            if let Err(error) = self.swap_file.write(&self.text_buffer) {
+                self.status_message = Some(format!("Can't write the swap file: {}", error));
             }
         }
     }
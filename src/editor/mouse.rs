@@ -0,0 +1,66 @@
+use super::selection::SelectionKind;
+use super::Editor;
+use crate::text_buffer::Position;
+use std::cmp::min;
+
+// Rows scrolled by a notch of the mouse wheel.
+pub const SCROLL_ROWS: usize = 3;
+
+/* The left button places the cursor, dragging selects from where the button was pressed,
+and the wheel scrolls the view, taking the cursor along when it would leave the screen. */
+impl Editor {
+    /* Position of the buffer drawn at a cell of the screen, None on the status bar.
+    Cells past the end of a row or below the last one give the nearest position. */
+    fn position_at_cell(&self, col: usize, row: usize) -> Option<Position> {
+        if row >= self.terminal.get_last_row() {
+            return None;
+        }
+        let row = min(row + self.text_buffer_row_offset, self.text_buffer.get_lenght() - 1);
+        let col = self
+            .text_buffer
+            .col_at_display_col(col + self.text_buffer_col_offset, row, self.tab_width);
+        Some(Position::new(col, row))
+    }
+
+    // A click leaves the selection and keeps only one cursor.
+    pub(super) fn mouse_clicked(&mut self, col: usize, row: usize) {
+        let position = match self.position_at_cell(col, row) {
+            Some(position) => position,
+            None => return,
+        };
+        self.pending_count = None;
+        if self.current_mode.is_selection_mode() {
+            self.switch_mode_to_navigation();
+        }
+        self.clear_secondary_cursors();
+        self.move_cursor_at(position.col, position.row);
+    }
+    // The selection starts at the cursor placed by the click, from the navigation or the edition mode.
+    pub(super) fn mouse_dragged(&mut self, col: usize, row: usize) {
+        let row = min(row, self.terminal.get_last_row().saturating_sub(1));
+        let position = match self.position_at_cell(col, row) {
+            Some(position) => position,
+            None => return,
+        };
+        if !self.current_mode.is_selection_mode() {
+            if self.current_mode.is_edition_mode() {
+                self.switch_mode_to_navigation();
+            }
+            self.select(SelectionKind::Char);
+        }
+        self.move_cursor_at(position.col, position.row);
+    }
+    pub(super) fn mouse_scrolled(&mut self, down: bool) {
+        let offset = if down {
+            min(self.text_buffer_row_offset + SCROLL_ROWS, self.text_buffer.get_lenght() - 1)
+        } else {
+            self.text_buffer_row_offset.saturating_sub(SCROLL_ROWS)
+        };
+        self.text_buffer_row_offset = offset;
+        let last_visible_row = min(offset + self.get_page_height(), self.text_buffer.get_lenght()) - 1;
+        let row = self.current_row_position().clamp(offset, last_visible_row);
+        if row != self.current_row_position() {
+            self.move_cursor_to_row(row);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use super::Editor;
+use crate::text_buffer::{BufferEdit, Position, SearchDirection, SearchPattern};
+
+/* Besides the cursor followed by the view (the primary one), the editor may hold secondary cursors.
+Typing acts at every cursor: the action is run at each of them in turn, and the edits it makes
+move the other cursors the way they move the text around them. Cursors ending at the same place are merged. */
+
+// Where a position of the buffer goes after `edit`. A position into the removed text goes after the inserted one.
+pub(crate) fn shift_position(position: Position, edit: &BufferEdit) -> Position {
+    if position < edit.removed.start {
+        position
+    } else if position < edit.removed.end {
+        edit.inserted.end
+    } else if position.row == edit.removed.end.row {
+        Position::new(
+            edit.inserted.end.col + position.col - edit.removed.end.col,
+            edit.inserted.end.row,
+        )
+    } else {
+        Position::new(position.col, (position.row as isize + edit.row_shift()) as usize)
+    }
+}
+
+pub(super) fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Editor {
+    pub(super) fn primary_cursor(&self) -> Position {
+        Position::new(self.current_col_position(), self.current_row_position())
+    }
+    pub(super) fn has_secondary_cursors(&self) -> bool {
+        !self.secondary_cursors.is_empty()
+    }
+    pub(super) fn clear_secondary_cursors(&mut self) {
+        self.secondary_cursors.clear();
+    }
+    // Secondary cursors of a row, to draw them.
+    pub(super) fn secondary_cursor_cols(&self, row: usize) -> Vec<usize> {
+        self.secondary_cursors
+            .iter()
+            .filter(|cursor| cursor.row == row)
+            .map(|cursor| cursor.col)
+            .collect()
+    }
+
+    /* Runs `action` at every cursor, the primary one first. The edits are tracked to move the other
+    cursors along. The view stays where it was, only scrolled to show the primary cursor. */
+    pub(super) fn at_every_cursor(&mut self, action: impl Fn(&mut Self)) {
+        if !self.has_secondary_cursors() {
+            action(self);
+            return;
+        }
+        let offsets = (self.text_buffer_row_offset, self.text_buffer_col_offset);
+        let mut cursors = vec![self.primary_cursor()];
+        cursors.extend(self.secondary_cursors.iter().map(|cursor| self.text_buffer.clamp_position(*cursor)));
+        let observer = self.text_buffer.observe_edits();
+        for i in 0..cursors.len() {
+            self.cursor.set_col_row(cursors[i].col, cursors[i].row);
+            action(self);
+            cursors[i] = self.primary_cursor();
+            for edit in self.text_buffer.take_edits(observer) {
+                for (j, cursor) in cursors.iter_mut().enumerate() {
+                    if j != i {
+                        *cursor = shift_position(*cursor, &edit);
+                    }
+                }
+            }
+        }
+        self.text_buffer.stop_observing_edits(observer);
+        self.text_buffer_row_offset = offsets.0;
+        self.text_buffer_col_offset = offsets.1;
+        self.move_cursor_at(cursors[0].col, cursors[0].row);
+        self.secondary_cursors = cursors.split_off(1);
+        self.merge_cursors();
+    }
+    fn merge_cursors(&mut self) {
+        let primary = self.primary_cursor();
+        self.secondary_cursors.sort();
+        self.secondary_cursors.dedup();
+        self.secondary_cursors.retain(|cursor| *cursor != primary);
+    }
+    fn add_cursor(&mut self, position: Position) {
+        self.secondary_cursors.push(position);
+        self.merge_cursors();
+        self.status_message = Some(format!("{} cursors", self.secondary_cursors.len() + 1));
+    }
+
+    // commands
+    /* Adds a cursor on the row below the lowest cursor (above the highest one),
+    at the column of the screen of the primary cursor, so that short rows in between don't shift it. */
+    pub(super) fn add_cursor_vertically(&mut self, below: bool) {
+        let primary = self.primary_cursor();
+        let cursors = self.secondary_cursors.iter().chain(std::iter::once(&primary));
+        let from = if below { cursors.max() } else { cursors.min() };
+        let from = match from {
+            Some(from) => *from,
+            None => return,
+        };
+        let row = if below {
+            from.row + 1
+        } else {
+            match from.row.checked_sub(1) {
+                Some(row) => row,
+                None => return,
+            }
+        };
+        if row >= self.text_buffer.get_lenght() {
+            return;
+        }
+        let display_col = self.cursor_display_col();
+        let col = self.text_buffer.col_at_display_col(display_col, row, self.tab_width);
+        self.add_cursor(Position::new(col, row));
+    }
+    /* Adds a cursor at the next occurrence of the word under the primary cursor, after the lowest cursor,
+    the primary one included. The new cursor is at the same place into the word. */
+    pub(super) fn add_cursor_at_next_occurrence(&mut self) {
+        let primary = self.primary_cursor();
+        let row: Vec<char> = self.text_buffer.borrow_row_at(primary.row).chars().collect();
+        let mut start = primary.col;
+        while start > 0 && is_word_char(row[start - 1]) {
+            start -= 1;
+        }
+        let mut end = primary.col;
+        while end < row.len() && is_word_char(row[end]) {
+            end += 1;
+        }
+        if start == end {
+            self.status_message = Some(String::from("No word under the cursor"));
+            return;
+        }
+        let word: String = row[start..end].iter().collect();
+        let pattern = match SearchPattern::new(&format!(r"\b{}\b", regex::escape(&word)), true, true) {
+            Ok(pattern) => pattern,
+            Err(_) => return,
+        };
+        let last = self.secondary_cursors.iter().copied().fold(primary, std::cmp::max);
+        let found = self
+            .text_buffer
+            .search(&pattern, last.col.saturating_sub(primary.col - start), last.row, SearchDirection::Forward);
+        let position = found.map(|found| Position::new(found.start_col + primary.col - start, found.row));
+        match position {
+            Some(position) if position != primary && !self.secondary_cursors.contains(&position) => {
+                self.add_cursor(position)
+            }
+            _ => self.status_message = Some(format!("No other occurrence of {}", word)),
+        }
+    }
+    // Puts a cursor at every match of the last search, the primary one at the first match from the cursor.
+    pub(super) fn add_cursors_at_matches(&mut self) {
+        let pattern = match &self.last_search {
+            Some(last_search) => last_search.pattern.clone(),
+            None => {
+                self.status_message = Some(String::from("No previous search"));
+                return;
+            }
+        };
+        let matches: Vec<Position> = self
+            .text_buffer
+            .find_all(&pattern)
+            .iter()
+            .map(|found| Position::new(found.start_col, found.row))
+            .collect();
+        if matches.is_empty() {
+            self.status_message = Some(String::from("No match"));
+            return;
+        }
+        let primary = self.primary_cursor();
+        let first = matches.iter().position(|found| *found >= primary).unwrap_or(0);
+        self.move_cursor_at(matches[first].col, matches[first].row);
+        self.secondary_cursors = matches;
+        self.merge_cursors();
+        self.status_message = Some(format!("{} cursors", self.secondary_cursors.len() + 1));
+    }
+}
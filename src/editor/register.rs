@@ -0,0 +1,236 @@
+use super::selection::{Selection, SelectionKind};
+use super::Editor;
+use crate::text_buffer::{Position, Range};
+use std::cmp::min;
+use std::collections::{HashMap, VecDeque};
+
+// Number of recent copies and deletions kept into the numbered registers.
+const HISTORY_LENGHT: usize = 10;
+
+// Text copied or deleted. A block is stored as its rows joined by '\n', the rows of a linewise text too.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Copied {
+    pub text: String,
+    pub kind: SelectionKind,
+}
+
+/* The internal clipboard. Text goes into the unnamed register, used when no register is given,
+and into the history of recent copies and deletions, read through the registers '0' (the last one) to '9'.
+The letters are the named registers, written only when they are explicitly given.
+The register '+' is the clipboard of the system (see `clipboard`). */
+pub struct Registers {
+    unnamed: Option<Copied>,
+    named: HashMap<char, Copied>,
+    history: VecDeque<Copied>,
+}
+
+impl Copied {
+    // What was copied, to tell the user.
+    pub fn describe(&self) -> String {
+        let (count, unit) = match self.kind {
+            SelectionKind::Char => (self.text.chars().count(), "char"),
+            SelectionKind::Line | SelectionKind::Block => (self.text.split('\n').count(), "row"),
+        };
+        format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+    }
+}
+
+impl Registers {
+    // constructor
+    pub fn new() -> Self {
+        Self {
+            unnamed: None,
+            named: HashMap::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn is_valid_name(name: char) -> bool {
+        name.is_ascii_lowercase() || name.is_ascii_digit() || name == '+'
+    }
+    // Copied text: it goes into the given register too, if any.
+    pub fn store(&mut self, name: Option<char>, copied: Copied) {
+        if let Some(name) = name.filter(char::is_ascii_lowercase) {
+            self.named.insert(name, copied.clone());
+        }
+        self.push_history(copied.clone());
+        self.unnamed = Some(copied);
+    }
+    // Deleted text is only kept into the history, so that deleting doesn't lose what was copied.
+    pub fn store_deleted(&mut self, copied: Copied) {
+        self.push_history(copied);
+    }
+    pub fn get(&self, name: Option<char>) -> Option<&Copied> {
+        match name {
+            None => self.unnamed.as_ref(),
+            Some(digit @ '0'..='9') => self.history.get(digit as usize - '0' as usize),
+            Some(letter) => self.named.get(&letter),
+        }
+    }
+
+    fn push_history(&mut self, copied: Copied) {
+        self.history.push_front(copied);
+        self.history.truncate(HISTORY_LENGHT);
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Editor {
+    // Asks for the register used by the next copy, cut or paste.
+    pub(super) fn choose_register(&mut self) {
+        let choices: Vec<char> = ('+'..='z').filter(|name| Registers::is_valid_name(*name)).collect();
+        self.pending_register =
+            self.ask_user_for_char("Register (a-z, 0-9 for the history, + for the system clipboard): ", &choices);
+    }
+    /* The system clipboard is read if possible, a text ending with a line break being pasted as rows.
+    When it can't be read, the unnamed register is pasted instead. */
+    fn take_register(&mut self) -> Option<Copied> {
+        let name = self.pending_register.take();
+        if name == Some('+') {
+            if let Some(text) = self.system_clipboard.as_mut().and_then(|clipboard| clipboard.paste()) {
+                return Some(match text.strip_suffix('\n') {
+                    Some(rows) => Copied {
+                        text: rows.to_string(),
+                        kind: SelectionKind::Line,
+                    },
+                    None => Copied {
+                        text,
+                        kind: SelectionKind::Char,
+                    },
+                });
+            }
+        }
+        let name = name.filter(|name| *name != '+');
+        let copied = self.registers.get(name).cloned();
+        if copied.is_none() {
+            self.status_message = Some(match name {
+                Some(name) => format!("The register {} is empty", name),
+                None => String::from("Nothing copied yet"),
+            });
+        }
+        copied
+    }
+
+    /* Pastes before or after the cursor, in one undo step: a charwise text into the row,
+    a linewise text as rows above or below the cursor's row, a block on the rows from the cursor's one,
+    at the same column of the screen. */
+    pub(super) fn paste(&mut self, after: bool) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let copied = match self.take_register() {
+            Some(copied) => copied,
+            None => return,
+        };
+        let row = self.current_row_position();
+        let col = if after {
+            self.text_buffer.next_grapheme_col(self.current_col_position(), row)
+        } else {
+            self.current_col_position()
+        };
+        self.text_buffer.commit_transaction();
+        let cursor = match copied.kind {
+            SelectionKind::Char => self.text_buffer.insert_str(Position::new(col, row), &copied.text),
+            SelectionKind::Line if after => {
+                let end_of_row = Position::new(self.text_buffer.get_lenght_of_row(row), row);
+                self.text_buffer.insert_str(end_of_row, &format!("\n{}", copied.text));
+                Position::new(0, row + 1)
+            }
+            SelectionKind::Line => {
+                self.text_buffer.insert_str(Position::new(0, row), &format!("{}\n", copied.text));
+                Position::new(0, row)
+            }
+            SelectionKind::Block => self.paste_block(&copied.text, Position::new(col, row)),
+        };
+        self.text_buffer.commit_transaction();
+        self.move_cursor_at(cursor.col, cursor.row);
+    }
+    // In edition mode, the unnamed register is pasted at the cursor as it is, the cursor goes after it.
+    pub(super) fn paste_at_cursor(&mut self) {
+        let copied = match self.take_register() {
+            Some(copied) => copied,
+            None => return,
+        };
+        let cursor = Position::new(self.current_col_position(), self.current_row_position());
+        let end = self.text_buffer.insert_str(cursor, &copied.text);
+        self.move_cursor_at(end.col, end.row);
+    }
+    // Rows shorter than the column of the block are filled with spaces, missing rows are added.
+    fn paste_block(&mut self, text: &str, position: Position) -> Position {
+        let display_col = self.text_buffer.display_col_of(position.col, position.row, self.tab_width);
+        for (i, block_row) in text.split('\n').enumerate() {
+            let row = position.row + i;
+            if row >= self.text_buffer.get_lenght() {
+                self.text_buffer.insert_str(self.text_buffer.end_position(), "\n");
+            }
+            let row_width = self
+                .text_buffer
+                .display_col_of(self.text_buffer.get_lenght_of_row(row), row, self.tab_width);
+            let padding = " ".repeat(display_col.saturating_sub(row_width));
+            let col = self.text_buffer.col_at_display_col(display_col, row, self.tab_width);
+            self.text_buffer
+                .insert_str(Position::new(col, row), &format!("{}{}", padding, block_row));
+        }
+        position
+    }
+    // Replaces the selection by the register.
+    pub(super) fn paste_over_selection(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        if let Some(copied) = self.take_register() {
+            self.replace_selection_with(&copied.text);
+        }
+    }
+
+    // A copy goes into the registers and to the clipboard of the system. Rows are sent with their line breaks.
+    pub(super) fn store_copied(&mut self, copied: Copied) {
+        if let Some(clipboard) = &mut self.system_clipboard {
+            let text = match copied.kind {
+                SelectionKind::Line => format!("{}\n", copied.text),
+                SelectionKind::Char | SelectionKind::Block => copied.text.clone(),
+            };
+            if let Err(error) = clipboard.copy(&text, &mut self.terminal) {
+                self.status_message = Some(format!("Can't copy to the clipboard ({}): {}", clipboard.get_name(), error));
+            }
+        }
+        self.registers.store(self.pending_register.take(), copied);
+    }
+    // The row of the cursor and the following ones, `count` rows in all.
+    pub(super) fn copy_rows(&mut self, count: usize) {
+        let first_row = self.current_row_position();
+        let last_row = min(first_row + count.max(1), self.text_buffer.get_lenght()) - 1;
+        let end = Position::new(self.text_buffer.get_lenght_of_row(last_row), last_row);
+        let copied = Copied {
+            text: self.text_buffer.slice(Range::new(Position::new(0, first_row), end)),
+            kind: SelectionKind::Line,
+        };
+        self.status_message = Some(format!("{} copied", copied.describe()));
+        self.store_copied(copied);
+    }
+    // The rows are removed the way a selection of them would be.
+    pub(super) fn cut_rows(&mut self, count: usize) {
+        if self.refuse_read_only() {
+            return;
+        }
+        self.copy_rows(count);
+        let first_row = self.current_row_position();
+        let last_row = min(first_row + count.max(1), self.text_buffer.get_lenght()) - 1;
+        self.status_message = Some(format!(
+            "{} row{} cut",
+            last_row - first_row + 1,
+            if last_row == first_row { "" } else { "s" }
+        ));
+        self.selection = Some(Selection {
+            anchor: Position::new(0, first_row),
+            kind: SelectionKind::Line,
+        });
+        self.move_cursor_at(0, last_row);
+        self.replace_selection_with("");
+    }
+}
@@ -0,0 +1,171 @@
+use super::Editor;
+use crate::terminal::event::{Event, Key};
+use crate::text_buffer::{SearchDirection, SearchPattern};
+use std::cmp::min;
+
+// The last validated search, repeated by the next/previous match commands.
+pub struct LastSearch {
+    pub pattern: SearchPattern,
+    pub direction: SearchDirection,
+}
+
+impl Editor {
+    /* Incremental search: the cursor jumps to the first match while the query is typed.
+    Enter keeps the cursor there and returns true, escape brings it back to where the search started.
+    Ctrl + r switches between literal text and regular expression, Ctrl + t toggles case sensitivity. */
+    pub(super) fn search_prompt(&mut self, direction: SearchDirection) -> bool {
+        let origin = (self.current_col_position(), self.current_row_position());
+        let origin_offsets = (self.text_buffer_row_offset, self.text_buffer_col_offset);
+        let mut query = String::new();
+        let mut is_regex = false;
+        let mut case_sensitive = false;
+        loop {
+            self.cursor.set_col_row(origin.0, origin.1);
+            self.text_buffer_row_offset = origin_offsets.0;
+            self.text_buffer_col_offset = origin_offsets.1;
+            let mut pattern = None;
+            let mut state = "";
+            if !query.is_empty() {
+                match SearchPattern::new(&query, is_regex, case_sensitive) {
+                    Ok(new_pattern) => {
+                        match self.text_buffer.search(&new_pattern, origin.0, origin.1, direction) {
+                            Some(found) => self.move_cursor_at(found.start_col, found.row),
+                            None => state = " (no match)",
+                        }
+                        pattern = Some(new_pattern);
+                    }
+                    Err(_) => state = " (invalid regex)",
+                }
+            }
+
+            self.draw();
+            self.terminal.move_cursor_at(0, self.terminal.get_last_row() - 1);
+            self.terminal.clear_current_line();
+            let prompt = format!(
+                "{}{}{} [{}, {}]",
+                if direction == SearchDirection::Forward { '/' } else { '?' },
+                query,
+                state,
+                if is_regex { "regex" } else { "text" },
+                if case_sensitive { "case" } else { "no case" },
+            );
+            let prompt: String = prompt.chars().take(self.terminal.get_size_col()).collect();
+            self.print_prompt(&prompt);
+            self.terminal.flush();
+
+            match self.read_event() {
+                Event::KeyPressed(Key::Char(c)) => query.push(c),
+                Event::KeyPressed(Key::Backspace) => {
+                    query.pop();
+                }
+                Event::KeyPressed(Key::Enter) => {
+                    return match pattern {
+                        Some(pattern) => {
+                            self.last_search = Some(LastSearch { pattern, direction });
+                            true
+                        }
+                        None => false,
+                    };
+                }
+                Event::KeyPressed(Key::Esc) | Event::CtrlKeyPressed(Key::Char('c')) => {
+                    self.cursor.set_col_row(origin.0, origin.1);
+                    self.text_buffer_row_offset = origin_offsets.0;
+                    self.text_buffer_col_offset = origin_offsets.1;
+                    return false;
+                }
+                Event::CtrlKeyPressed(Key::Char('r')) => is_regex = !is_regex,
+                Event::CtrlKeyPressed(Key::Char('t')) => case_sensitive = !case_sensitive,
+                _ => (),
+            }
+        }
+    }
+    /* Replaces the matches of a search, from the top of the buffer. For every match the user
+    answers yes, no, all (this one and the following ones without asking) or quit.
+    All the replacements are undone in one step. */
+    pub(super) fn replace_prompt(&mut self) {
+        if self.text_buffer.is_read_only() {
+            self.status_message = Some(String::from("The buffer is read-only"));
+            return;
+        }
+        if !self.search_prompt(SearchDirection::Forward) {
+            return;
+        }
+        let pattern = match &self.last_search {
+            Some(last_search) => last_search.pattern.clone(),
+            None => return,
+        };
+        let replacement = match self.ask_user_for_text("With: ") {
+            Some(replacement) => replacement,
+            None => return,
+        };
+
+        self.text_buffer.commit_transaction();
+        let mut count = 0;
+        let mut row = 0;
+        /* The matches of a row are found before replacing any of them, so that a replaced text
+        isn't matched again: a replacement shifts the following matches by `shift`. */
+        'rows: while row < self.text_buffer.get_lenght() {
+            let (row_text, matches) = self.text_buffer.find_in_row(&pattern, row);
+            let mut shift = 0;
+            for (index, found) in matches.iter().enumerate() {
+                self.move_cursor_at((found.start_col as isize + shift) as usize, row);
+                self.draw();
+                match self.ask_user_for_char("Replace? (y)es, (n)o, (a)ll, (q)uit ", &['y', 'n', 'a', 'q']) {
+                    Some('y') => {
+                        let found = std::slice::from_ref(found);
+                        shift += self
+                            .text_buffer
+                            .replace_row_matches(&pattern, &row_text, found, shift, &replacement);
+                        count += 1;
+                    }
+                    Some('n') => (),
+                    Some('a') => {
+                        let rest = &matches[index..];
+                        self.text_buffer
+                            .replace_row_matches(&pattern, &row_text, rest, shift, &replacement);
+                        count += rest.len();
+                        if row + 1 < self.text_buffer.get_lenght() {
+                            count += self.text_buffer.replace_all_from(&pattern, &replacement, 0, row + 1);
+                        }
+                        break 'rows;
+                    }
+                    _ => break 'rows,
+                }
+            }
+            row += 1;
+        }
+        self.text_buffer.commit_transaction();
+        let row = min(self.current_row_position(), self.text_buffer.get_lenght() - 1);
+        let col = min(self.current_col_position(), self.text_buffer.get_lenght_of_row(row));
+        self.move_cursor_at(col, row);
+        self.status_message = Some(format!(
+            "{} replacement{}",
+            count,
+            if count == 1 { "" } else { "s" }
+        ));
+    }
+    // Jumps to the next match of the last search, or to the previous one if `reverse` is true.
+    pub(super) fn search_next(&mut self, reverse: bool) {
+        let last_search = match &self.last_search {
+            Some(last_search) => last_search,
+            None => {
+                self.status_message = Some(String::from("No previous search"));
+                return;
+            }
+        };
+        let direction = match (last_search.direction, reverse) {
+            (SearchDirection::Forward, false) | (SearchDirection::Backward, true) => SearchDirection::Forward,
+            _ => SearchDirection::Backward,
+        };
+        let found = self.text_buffer.search(
+            &last_search.pattern,
+            self.current_col_position(),
+            self.current_row_position(),
+            direction,
+        );
+        match found {
+            Some(found) => self.move_cursor_at(found.start_col, found.row),
+            None => self.status_message = Some(String::from("No match")),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use super::register::Copied;
+use super::Editor;
+use crate::text_buffer::{Position, Range};
+use std::cmp::{max, min};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SelectionKind {
+    // From the anchor to the cursor, both included.
+    Char,
+    // The whole rows between the anchor and the cursor.
+    Line,
+    // The rectangle between the anchor and the cursor, in columns of the screen.
+    Block,
+}
+
+/* The selection goes from where it started (the anchor) to the cursor, which moves freely:
+the anchor may be after the cursor. */
+pub struct Selection {
+    pub anchor: Position,
+    pub kind: SelectionKind,
+}
+
+impl Editor {
+    /* Starts a selection of the given kind at the cursor. During a selection, the same command
+    leaves it and the command of another kind changes the kind of the current selection. */
+    pub(super) fn select(&mut self, kind: SelectionKind) {
+        match &mut self.selection {
+            Some(selection) if selection.kind == kind => self.switch_mode_to_navigation(),
+            Some(selection) => selection.kind = kind,
+            None => {
+                let anchor = Position::new(self.current_col_position(), self.current_row_position());
+                self.selection = Some(Selection { anchor, kind });
+                self.switch_mode_to_selection();
+            }
+        }
+    }
+
+    /* The selected text, as ranges of the buffer: a single one for a char or line selection,
+    one for every row of a block. */
+    pub(crate) fn selection_ranges(&self) -> Vec<Range> {
+        let selection = match &self.selection {
+            Some(selection) => selection,
+            None => return Vec::new(),
+        };
+        // The text may have changed under the anchor (undo, reload).
+        let anchor = self.text_buffer.clamp_position(selection.anchor);
+        let cursor = Position::new(self.current_col_position(), self.current_row_position());
+        let first = min(anchor, cursor);
+        let last = max(anchor, cursor);
+        match selection.kind {
+            // The char under the cursor is selected, and so is the line break if the cursor is at the end of a row.
+            SelectionKind::Char => {
+                let end = if last.col < self.text_buffer.get_lenght_of_row(last.row) {
+                    Position::new(self.text_buffer.next_grapheme_col(last.col, last.row), last.row)
+                } else {
+                    self.start_of_next_row(last.row)
+                };
+                vec![Range::new(first, end)]
+            }
+            SelectionKind::Line => vec![Range::new(Position::new(0, first.row), self.start_of_next_row(last.row))],
+            SelectionKind::Block => {
+                let left = min(
+                    self.text_buffer.display_col_of(anchor.col, anchor.row, self.tab_width),
+                    self.cursor_display_col(),
+                );
+                // The char under the rightmost side is included, even if it's wider than one column.
+                let right = max(self.display_end_of(anchor), self.display_end_of(cursor));
+                (first.row..=last.row)
+                    .map(|row| {
+                        Range::new(
+                            Position::new(self.text_buffer.col_at_display_col(left, row, self.tab_width), row),
+                            Position::new(self.text_buffer.col_at_display_col(right, row, self.tab_width), row),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+    // After the line break ending the row, the end of the buffer for the last row.
+    fn start_of_next_row(&self, row: usize) -> Position {
+        if row + 1 < self.text_buffer.get_lenght() {
+            Position::new(0, row + 1)
+        } else {
+            self.text_buffer.end_position()
+        }
+    }
+    // Column of the screen right after the char at `position`, or after the end of its row.
+    fn display_end_of(&self, position: Position) -> usize {
+        let next_col = self.text_buffer.next_grapheme_col(position.col, position.row);
+        let display_col = self.text_buffer.display_col_of(next_col, position.row, self.tab_width);
+        if next_col == position.col {
+            display_col + 1
+        } else {
+            display_col
+        }
+    }
+    /* Selected columns of a row, to highlight them: from the first one included to the last one excluded,
+    and whether the line break ending the row is selected. */
+    pub(super) fn selected_cols(&self, row: usize) -> Option<(usize, usize, bool)> {
+        self.selection_ranges()
+            .into_iter()
+            .find(|range| range.start.row <= row && row <= range.end.row)
+            .map(|range| {
+                let start = if range.start.row == row { range.start.col } else { 0 };
+                let end = if range.end.row == row {
+                    range.end.col
+                } else {
+                    self.text_buffer.get_lenght_of_row(row)
+                };
+                (start, end, range.end.row > row)
+            })
+    }
+
+    // First and last selected rows.
+    fn selected_rows(&self) -> Option<(usize, usize)> {
+        let anchor_row = self.text_buffer.clamp_position(self.selection.as_ref()?.anchor).row;
+        let cursor_row = self.current_row_position();
+        Some((min(anchor_row, cursor_row), max(anchor_row, cursor_row)))
+    }
+    // Content of the rows, without the line break ending the last one.
+    fn rows_range(&self, first_row: usize, last_row: usize) -> Range {
+        Range::new(
+            Position::new(0, first_row),
+            Position::new(self.text_buffer.get_lenght_of_row(last_row), last_row),
+        )
+    }
+    fn selected_text(&self) -> Option<Copied> {
+        let kind = self.selection.as_ref()?.kind;
+        let ranges = self.selection_ranges();
+        let text = match kind {
+            SelectionKind::Char => self.text_buffer.slice(ranges[0]),
+            SelectionKind::Line => {
+                let (first_row, last_row) = self.selected_rows()?;
+                self.text_buffer.slice(self.rows_range(first_row, last_row))
+            }
+            SelectionKind::Block => ranges
+                .iter()
+                .map(|range| self.text_buffer.slice(*range))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        };
+        Some(Copied { text, kind })
+    }
+
+    // operations
+    pub(super) fn copy_selection(&mut self) {
+        if let Some(copied) = self.selected_text() {
+            self.status_message = Some(format!("{} copied", copied.describe()));
+            self.store_copied(copied);
+        }
+        let start = self.selection_ranges().first().map_or(Position::default(), |range| range.start);
+        self.leave_selection_at(start);
+    }
+    pub(super) fn cut_selection(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        if let Some(copied) = self.selected_text() {
+            self.status_message = Some(format!("{} cut", copied.describe()));
+            self.store_copied(copied);
+        }
+        self.replace_selection_with("");
+    }
+    pub(super) fn delete_selection(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        if let Some(copied) = self.selected_text() {
+            self.registers.store_deleted(copied);
+        }
+        self.replace_selection_with("");
+    }
+    // Replaces the selection by a text typed by the user. Every row of a block gets the text.
+    pub(super) fn replace_selection(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        if let Some(text) = self.ask_user_for_text("Replace with: ") {
+            self.replace_selection_with(&text);
+        }
+    }
+
+    /* Replaces the selected text by `text`, in one undo step, and leaves the selection mode.
+    Deleting whole rows also deletes their line breaks: the following rows move up. */
+    pub(super) fn replace_selection_with(&mut self, text: &str) {
+        let kind = match &self.selection {
+            Some(selection) => selection.kind,
+            None => return,
+        };
+        let ranges = self.selection_ranges();
+        let start = ranges[0].start;
+        self.text_buffer.commit_transaction();
+        match (kind, self.selected_rows()) {
+            (SelectionKind::Line, Some((first_row, last_row))) if text.is_empty() => {
+                if last_row + 1 < self.text_buffer.get_lenght() {
+                    self.text_buffer
+                        .delete_range(Range::new(Position::new(0, first_row), Position::new(0, last_row + 1)));
+                } else if first_row > 0 {
+                    // The last rows of the buffer: the line break before them goes instead.
+                    let previous_row = first_row - 1;
+                    let previous_end = Position::new(self.text_buffer.get_lenght_of_row(previous_row), previous_row);
+                    self.text_buffer
+                        .delete_range(Range::new(previous_end, self.text_buffer.end_position()));
+                } else {
+                    self.text_buffer.delete_range(self.rows_range(first_row, last_row));
+                }
+            }
+            (SelectionKind::Line, Some((first_row, last_row))) => {
+                self.text_buffer.replace_range(self.rows_range(first_row, last_row), text);
+            }
+            // From the bottom, so that the ranges left to edit don't move.
+            _ => {
+                for range in ranges.iter().rev() {
+                    self.text_buffer.replace_range(*range, text);
+                }
+            }
+        }
+        self.text_buffer.commit_transaction();
+        self.leave_selection_at(self.text_buffer.clamp_position(start));
+    }
+    fn leave_selection_at(&mut self, position: Position) {
+        self.switch_mode_to_navigation();
+        self.move_cursor_at(position.col, position.row);
+    }
+}
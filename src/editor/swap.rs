@@ -0,0 +1,274 @@
+use crate::text_buffer::{write_private_atomically, Buffer};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// Unsaved edits are written to the swap file once the buffer has been left unchanged for this long.
+pub const SWAP_WRITE_DELAY: Duration = Duration::from_secs(2);
+const SWAP_EXTENSION: &str = "ante-swp";
+const SWAP_HEADER: &str = "ante swap file v2";
+// Swap files written before the owner was stored in them.
+const OLD_SWAP_HEADER: &str = "ante swap file";
+
+/* Journal of the unsaved edits of the text buffer, used to recover them after a crash.
+It's written next to the edited file (or into the state directory if that's not possible,
+or if the buffer has no path yet), and removed on a clean save or quit. */
+pub struct SwapFile {
+    // Where the swap file has been written, if it has been.
+    written_path: Option<PathBuf>,
+    written_version: usize,
+    last_write: Instant,
+}
+
+/* The session a swap file belongs to: a swap file is stale only once its process is gone,
+the one of another running session must be left alone. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct SwapOwner {
+    pub pid: u32,
+    pub hostname: String,
+}
+
+// What a swap file holds.
+pub struct SwapContent {
+    // The edited file, None for a buffer without path.
+    pub file_path: Option<PathBuf>,
+    // None for the swap files of older versions.
+    pub owner: Option<SwapOwner>,
+    pub text: String,
+}
+
+impl SwapOwner {
+    pub fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            hostname: hostname(),
+        }
+    }
+    // Written as `pid hostname` on a line of the swap file.
+    fn parse(line: &str) -> Option<Self> {
+        let (pid, hostname) = line.split_once(' ')?;
+        Some(Self {
+            pid: pid.parse().ok()?,
+            hostname: hostname.to_string(),
+        })
+    }
+    // The process of another computer can't be checked, it's taken as running.
+    pub fn is_running(&self) -> bool {
+        self.hostname != hostname() || self.pid == std::process::id() || is_process_running(self.pid)
+    }
+}
+
+#[cfg(unix)]
+fn is_process_running(pid: u32) -> bool {
+    let pid = match std::convert::TryFrom::try_from(pid) {
+        Ok(pid) => pid,
+        Err(_) => return false,
+    };
+    // The signal 0 only checks that the process exists. It may exist and belong to someone else.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+// Without a way to check, the swap files of other processes are taken as stale, as they were before.
+#[cfg(not(unix))]
+fn is_process_running(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    if unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) } != 0 {
+        return String::new();
+    }
+    let lenght = buffer.iter().position(|byte| *byte == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..lenght]).into_owned()
+}
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_default()
+}
+
+impl SwapFile {
+    // constructor
+    pub fn new() -> Self {
+        Self {
+            written_path: None,
+            written_version: 0,
+            last_write: Instant::now(),
+        }
+    }
+
+    // accessors
+    // True when the buffer holds edits which are neither saved nor in the swap file.
+    pub fn is_outdated(&self, buffer: &Buffer) -> bool {
+        buffer.has_unsaved_changes() && (self.written_path.is_none() || self.written_version != buffer.get_version())
+    }
+    pub fn is_write_delay_elapsed(&self) -> bool {
+        self.last_write.elapsed() >= SWAP_WRITE_DELAY
+    }
+
+    // write
+    /* The swap file of another running session editing the same file is left alone:
+    the next place is used instead. */
+    pub fn write(&mut self, buffer: &Buffer) -> std::io::Result<()> {
+        self.last_write = Instant::now();
+        let mut result = Err(std::io::Error::other("no place for the swap file"));
+        let state_directory = state_directory();
+        for path in candidate_paths(buffer.get_path().as_deref()) {
+            if let Some(owner) = other_session_owning(&path) {
+                result = Err(std::io::Error::other(format!(
+                    "{} is used by another session (process {} on {})",
+                    path.display(),
+                    owner.pid,
+                    owner.hostname
+                )));
+                continue;
+            }
+            // The state directory may not exist yet.
+            if let Some(directory) = &state_directory {
+                if path.starts_with(directory) {
+                    let _ = std::fs::create_dir_all(directory);
+                }
+            }
+            result = write_private_atomically(&path, |writer| {
+                let owner = SwapOwner::current();
+                writeln!(writer, "{}", SWAP_HEADER)?;
+                writeln!(writer, "{}", buffer.get_path_as_str().unwrap_or(""))?;
+                writeln!(writer, "{} {}", owner.pid, owner.hostname)?;
+                buffer.write_to(writer)
+            });
+            if result.is_ok() {
+                if self.written_path.as_ref().is_some_and(|written_path| *written_path != path) {
+                    self.remove();
+                }
+                self.written_path = Some(path);
+                self.written_version = buffer.get_version();
+                break;
+            }
+        }
+        result
+    }
+    pub fn remove(&mut self) {
+        if let Some(path) = self.written_path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl Default for SwapFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/* Looks for a swap file left by a previous session which crashed or was killed.
+For a buffer without path, the most recent swap file of an unnamed buffer is returned. */
+pub fn find_stale_swap_file(file_path: Option<&Path>) -> Option<PathBuf> {
+    match file_path {
+        Some(_) => candidate_paths(file_path)
+            .into_iter()
+            .find(|path| path.is_file() && is_stale(path)),
+        None => {
+            let own_name = unnamed_swap_file_name();
+            std::fs::read_dir(state_directory()?)
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    name.starts_with("unnamed-") && name.ends_with(SWAP_EXTENSION) && name != own_name
+                })
+                .filter(|entry| is_stale(&entry.path()))
+                .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+                .max()
+                .map(|(_, path)| path)
+        }
+    }
+}
+
+// The owner of the swap file at `swap_path`, if it's another session still running.
+fn other_session_owning(swap_path: &Path) -> Option<SwapOwner> {
+    let owner = read_swap_file(swap_path).ok()?.owner?;
+    if owner != SwapOwner::current() && owner.is_running() {
+        Some(owner)
+    } else {
+        None
+    }
+}
+
+// A swap file which can't be read isn't offered.
+fn is_stale(swap_path: &Path) -> bool {
+    match read_swap_file(swap_path) {
+        Ok(content) => !content.owner.is_some_and(|owner| owner.is_running()),
+        Err(_) => false,
+    }
+}
+
+// What `SwapFile::write` wrote.
+pub fn read_swap_file(swap_path: &Path) -> std::io::Result<SwapContent> {
+    let mut reader = BufReader::new(std::fs::File::open(swap_path)?);
+    let mut header = String::new();
+    let mut file_path = String::new();
+    reader.read_line(&mut header)?;
+    reader.read_line(&mut file_path)?;
+    let owner = match header.trim_end_matches('\n') {
+        SWAP_HEADER => {
+            let mut owner = String::new();
+            reader.read_line(&mut owner)?;
+            SwapOwner::parse(owner.trim_end_matches('\n'))
+        }
+        OLD_SWAP_HEADER => None,
+        _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not an ante swap file")),
+    };
+    let file_path = file_path.trim_end_matches('\n');
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    Ok(SwapContent {
+        file_path: if file_path.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(file_path))
+        },
+        owner,
+        text,
+    })
+}
+
+/* $XDG_STATE_HOME/ante, or ~/.local/state/ante. Ante keeps there what must survive a session
+but doesn't belong next to the edited files. */
+pub fn state_directory() -> Option<PathBuf> {
+    match std::env::var_os("XDG_STATE_HOME") {
+        Some(directory) if !directory.is_empty() => Some(PathBuf::from(directory).join("ante")),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state").join("ante")),
+    }
+}
+
+// Where the swap file of a buffer may be, in order of preference.
+fn candidate_paths(file_path: Option<&Path>) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let state_directory = state_directory();
+    match file_path {
+        Some(file_path) => {
+            if let Some(file_name) = file_path.file_name() {
+                let swap_name = format!(".{}.{}", file_name.to_string_lossy(), SWAP_EXTENSION);
+                paths.push(file_path.with_file_name(swap_name));
+            }
+            if let Some(directory) = state_directory {
+                let absolute_path = std::env::current_dir()
+                    .map(|current_dir| current_dir.join(file_path))
+                    .unwrap_or_else(|_| file_path.to_path_buf());
+                let escaped_path = absolute_path.to_string_lossy().replace('%', "%%").replace('/', "%");
+                paths.push(directory.join(format!("{}.{}", escaped_path, SWAP_EXTENSION)));
+            }
+        }
+        None => {
+            if let Some(directory) = state_directory {
+                paths.push(directory.join(unnamed_swap_file_name()));
+            }
+        }
+    }
+    paths
+}
+
+fn unnamed_swap_file_name() -> String {
+    format!("unnamed-{}.{}", std::process::id(), SWAP_EXTENSION)
+}
@@ -37,9 +37,30 @@ pub use editor::Editor;
 mod tests {
     use super::*;
 
+    // An editor of a file holding `text`, the file being removed once loaded.
+    fn editor_of(name: &str, text: &str) -> editor::Editor {
+        let path = std::env::temp_dir().join(format!("ante-test-{}-{}", name, std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let editor = editor::Editor::new(Some(path.to_string_lossy().into_owned()), config::Config::default());
+        std::fs::remove_file(&path).unwrap();
+        editor
+    }
+    fn rows_of(editor: &editor::Editor) -> Vec<String> {
+        let buffer = editor.get_text_buffer();
+        (0..buffer.get_lenght()).map(|row| buffer.borrow_row_at(row).to_string()).collect()
+    }
+    fn press(editor: &mut editor::Editor, keys: &[terminal::event::Event]) {
+        for key in keys {
+            editor.process_event(key.clone());
+        }
+    }
+    fn key(c: char) -> terminal::event::Event {
+        terminal::event::Event::KeyPressed(terminal::event::Key::Char(c))
+    }
+
     #[test]
     fn init_editor() {
-        let _editor = editor::Editor::new(Option::None);
+        let _editor = editor::Editor::new(Option::None, config::Config::default());
     }
 
     #[test]
@@ -47,6 +68,909 @@ mod tests {
         let _buffer = text_buffer::Buffer::new_empty();
     }
 
+    #[test]
+    fn text_buffer_row_edits() {
+        let mut buffer = text_buffer::Buffer::new_empty();
+        for (i, c) in "hello".chars().enumerate() {
+            buffer.insert_char(i, 0, c);
+        }
+        let tail = buffer.remove_row_from(2, 0);
+        buffer.insert_row_at_with_vec(1, tail);
+        buffer.insert_row_at(2);
+        assert_eq!(buffer.get_lenght(), 3);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "he");
+        assert_eq!(buffer.borrow_row_at(1).to_string(), "llo");
+        assert!(buffer.row_is_empty(2));
+
+        buffer.remove_row_to_get_data(2);
+        let mut data = buffer.remove_row_to_get_data(1);
+        buffer.push_vec_to_row(0, &mut data);
+        buffer.delete_char(0, 0);
+        assert_eq!(buffer.get_lenght(), 1);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "ello");
+        assert_eq!(buffer.get_char_at(3, 0), 'o');
+
+        // Past the end of the row, there is nothing to delete.
+        buffer.delete_char(4, 0);
+        buffer.delete_char(10, 0);
+        assert_eq!(buffer.get_lenght(), 1);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "ello");
+    }
+
+    #[test]
+    fn text_buffer_undo_redo() {
+        let mut buffer = text_buffer::Buffer::new_empty();
+        for (i, c) in "ab".chars().enumerate() {
+            buffer.insert_char(i, 0, c);
+        }
+        buffer.commit_transaction();
+        buffer.insert_row_at_with_vec(1, vec!['c']);
+        buffer.delete_char(0, 0);
+
+        assert_eq!(buffer.undo(), Some((2, 0)));
+        assert_eq!(buffer.get_lenght(), 1);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "ab");
+        assert_eq!(buffer.undo(), Some((0, 0)));
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.undo(), None);
+
+        assert_eq!(buffer.redo(), Some((2, 0)));
+        assert_eq!(buffer.redo(), Some((0, 0)));
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "b");
+        assert_eq!(buffer.borrow_row_at(1).to_string(), "c");
+    }
+
+    #[test]
+    fn text_buffer_lossless_round_trip() {
+        for text in ["", "a", "a  \n\tb \n", "a\r\nb\r\n", "a\r\nb", "a\rb\r\n", "a\r\nb\n", "\n\n"] {
+            let buffer = text_buffer::Buffer::new_from_str(text);
+            let mut written = Vec::new();
+            buffer.write_to(&mut written).unwrap();
+            assert_eq!(String::from_utf8(written).unwrap(), text);
+        }
+        let mut buffer = text_buffer::Buffer::new_from_str("a\r\nb\r\n");
+        assert_eq!(buffer.get_lenght(), 2);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "a");
+        buffer.set_line_ending(text_buffer::LineEnding::Lf);
+        buffer.set_final_newline(false);
+        let mut written = Vec::new();
+        buffer.write_to(&mut written).unwrap();
+        assert_eq!(written, b"a\nb");
+    }
+
+    #[test]
+    fn text_buffer_load_errors() {
+        let directory = std::env::temp_dir();
+        assert!(matches!(
+            text_buffer::Buffer::new_from_file(directory.clone()),
+            Err(text_buffer::LoadError::IsADirectory)
+        ));
+
+        let invalid_utf8 = directory.join(format!("ante-test-invalid-utf8-{}", std::process::id()));
+        std::fs::write(&invalid_utf8, [0x61, 0xff, 0x0a]).unwrap();
+        assert!(matches!(
+            text_buffer::Buffer::new_from_file(invalid_utf8.clone()),
+            Err(text_buffer::LoadError::InvalidUtf8)
+        ));
+        let mut buffer = text_buffer::Buffer::new_read_only(invalid_utf8.clone());
+        assert!(matches!(buffer.save(), Err(text_buffer::SaveError::ReadOnly)));
+        // The file which failed to load isn't reported as modified by another program.
+        assert!(buffer.check_disk().is_none());
+        assert!(!buffer.has_unsaved_changes());
+        assert_eq!(std::fs::read(&invalid_utf8).unwrap(), [0x61, 0xff, 0x0a]);
+        std::fs::remove_file(&invalid_utf8).unwrap();
+
+        let new_file = text_buffer::Buffer::new_from_file(invalid_utf8).unwrap();
+        assert!(new_file.is_empty() && !new_file.is_read_only());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn text_buffer_atomic_save() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = std::env::temp_dir().join(format!("ante-test-save-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let target = directory.join("target");
+        let link = directory.join("link");
+        std::fs::write(&target, "old\n").unwrap();
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink("target", &link).unwrap();
+
+        let mut buffer = text_buffer::Buffer::new_from_file(link.clone()).unwrap();
+        buffer.insert_char(0, 0, 'n');
+        buffer.save().unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "nold\n");
+        assert_eq!(std::fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o640);
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 2);
+
+        let mut buffer = text_buffer::Buffer::new_empty();
+        assert!(buffer.save_as(directory.join("missing").join("file")).is_err());
+        assert!(buffer.get_path().is_none());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn text_buffer_external_modification() {
+        let path = std::env::temp_dir().join(format!("ante-test-external-{}", std::process::id()));
+        std::fs::write(&path, "one\n").unwrap();
+        let mut buffer = text_buffer::Buffer::new_from_file(path.clone()).unwrap();
+        buffer.insert_char(0, 0, '1');
+        buffer.save().unwrap();
+        assert!(buffer.check_disk().is_none());
+
+        std::fs::write(&path, "two\nlines\n").unwrap();
+        assert!(matches!(buffer.check_disk(), Some(text_buffer::DiskChange::Modified(_))));
+        assert!(matches!(buffer.save(), Err(text_buffer::SaveError::ModifiedOnDisk(_))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\nlines\n");
+
+        buffer.reload().unwrap();
+        assert_eq!(buffer.get_lenght(), 2);
+        assert!(buffer.check_disk().is_none());
+        buffer.undo();
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "1one");
+        buffer.save_overwriting().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1one\n");
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(buffer.check_disk(), Some(text_buffer::DiskChange::Deleted)));
+    }
+
+    #[test]
+    fn text_buffer_recovered_text() {
+        let path = std::env::temp_dir().join(format!("ante-test-recovered-{}", std::process::id()));
+        std::fs::write(&path, "one\n").unwrap();
+        let mut buffer = text_buffer::Buffer::new_from_file(path.clone()).unwrap();
+        // The text of a swap file replaces the one loaded, which is still the one on disk.
+        buffer.replace_text("one\nrecovered\n");
+        assert!(matches!(buffer.get_status(), text_buffer::BufferStatus::Unsaved));
+        assert!(buffer.check_disk().is_none());
+        buffer.save().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\nrecovered\n");
+        assert!(buffer.check_disk().is_none());
+        buffer.undo();
+        assert_eq!(buffer.get_lenght(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn editor_count() {
+        use terminal::event::{Event, Key};
+
+        let mut editor = editor_of("count", "one two three four\n");
+        let undo = Event::CtrlKeyPressed(Key::Char('z'));
+
+        // 0 doesn't start a count, it only adds to one.
+        let status = editor.get_status_bar_text();
+        editor.process_event(Event::KeyPressed(Key::Char('0')));
+        assert_eq!(editor.get_status_bar_text(), status);
+        editor.process_event(Event::KeyPressed(Key::Char('1')));
+        editor.process_event(Event::KeyPressed(Key::Char('0')));
+        assert!(editor.get_status_bar_text().starts_with(&format!("{} 10", status.split(' ').next().unwrap())));
+
+        // The count repeats the action, undone in one step.
+        editor.process_event(Event::KeyPressed(Key::Delete));
+        assert_eq!(rows_of(&editor)[0], "ree four");
+        assert!(!editor.get_status_bar_text().contains(" 10"));
+        editor.process_event(undo.clone());
+        assert_eq!(rows_of(&editor)[0], "one two three four");
+
+        // Each edit of the navigation mode is undone on its own.
+        press(&mut editor, &[key('d'), key('w'), key('d'), key('w')]);
+        assert_eq!(rows_of(&editor)[0], "three four");
+        editor.process_event(undo.clone());
+        assert_eq!(rows_of(&editor)[0], "two three four");
+
+        // Undo and redo are repeated too.
+        press(&mut editor, &[key('d'), key('w'), key('d'), key('w'), key('d'), key('w')]);
+        assert_eq!(rows_of(&editor)[0], "");
+        press(&mut editor, &[key('2'), undo]);
+        assert_eq!(rows_of(&editor)[0], "three four");
+        press(&mut editor, &[key('9'), key('9'), Event::CtrlKeyPressed(Key::Char('y'))]);
+        assert_eq!(rows_of(&editor)[0], "");
+    }
+
+    #[test]
+    fn editor_shift_position() {
+        use editor::multi_cursor::shift_position;
+        use text_buffer::{BufferEdit, Position, Range};
+
+        // "bc\nd" from (1, 0) to (1, 1) replaced by "X\nY\nZ".
+        let edit = BufferEdit {
+            removed: Range::new(Position::new(1, 0), Position::new(1, 1)),
+            removed_text: String::from("bc\nd"),
+            inserted: Range::new(Position::new(1, 0), Position::new(1, 2)),
+            inserted_text: String::from("X\nY\nZ"),
+        };
+        let shift = |col, row| shift_position(Position::new(col, row), &edit);
+        assert_eq!(shift(0, 0), Position::new(0, 0));
+        assert_eq!(shift(1, 0), Position::new(1, 2));
+        assert_eq!(shift(2, 0), Position::new(1, 2));
+        assert_eq!(shift(1, 1), Position::new(1, 2));
+        assert_eq!(shift(3, 1), Position::new(3, 2));
+        assert_eq!(shift(2, 3), Position::new(2, 4));
+    }
+
+    #[test]
+    fn editor_multiple_cursors() {
+        use terminal::event::{Event, Key};
+
+        let mut editor = editor_of("cursors", "a a\nfoo\nfoo\nfoo\nfoo\n");
+        // Another observer of the edits keeps getting them.
+        let observer = editor.get_text_buffer_mut().observe_edits();
+
+        // Deleting the first word brings the second cursor onto the first one: they are merged.
+        editor.process_event(Event::CtrlKeyPressed(Key::Char('d')));
+        assert!(editor.get_status_bar_text().contains(" 2 cursors"));
+        press(&mut editor, &[key('d'), key('w')]);
+        assert_eq!(rows_of(&editor)[0], "");
+        assert!(!editor.get_status_bar_text().contains("cursors"));
+
+        // The next occurrence is searched after the primary cursor, below the other one.
+        for _ in 0..3 {
+            editor.process_event(Event::KeyPressed(Key::DownArrow));
+        }
+        editor.process_event(Event::CtrlKeyPressed(Key::UpArrow));
+        editor.process_event(Event::CtrlKeyPressed(Key::Char('d')));
+        assert!(editor.get_status_bar_text().contains(" 3 cursors"));
+        press(&mut editor, &[key('d'), key('w')]);
+        assert_eq!(rows_of(&editor), ["", "foo", "", "", ""]);
+        let removed: Vec<String> = editor
+            .get_text_buffer_mut()
+            .take_edits(observer)
+            .into_iter()
+            .map(|edit| edit.removed_text)
+            .collect();
+        assert_eq!(removed, ["a ", "a", "foo", "foo", "foo"]);
+    }
+
+    #[test]
+    fn editor_base64() {
+        use editor::clipboard::encode_base64;
+
+        // The vectors of RFC 4648.
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (text, encoded) in vectors {
+            assert_eq!(encode_base64(text.as_bytes()), encoded);
+        }
+        assert_eq!(encode_base64("héllo €".as_bytes()), "aMOpbGxvIOKCrA==");
+    }
+
+    #[test]
+    fn editor_clipboard_detection() {
+        use editor::clipboard::detect;
+        use std::ffi::OsString;
+
+        let directory = std::env::temp_dir().join(format!("ante-test-clipboard-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("xclip"), "").unwrap();
+        let path = directory.to_string_lossy().into_owned();
+        let name_of = |variables: &[(&str, &str)]| {
+            let env = |name: &str| {
+                variables.iter().find(|(variable, _)| *variable == name).map(|(_, value)| OsString::from(value))
+            };
+            detect(None, None, env).map(|clipboard| clipboard.get_name().to_string())
+        };
+
+        // Without any clipboard, the copies only go to the internal registers.
+        assert_eq!(name_of(&[]), None);
+        assert_eq!(name_of(&[("DISPLAY", ":0"), ("WAYLAND_DISPLAY", "")]), None);
+        assert_eq!(name_of(&[("DISPLAY", ":0"), ("PATH", &path)]).as_deref(), Some("xclip"));
+        assert_eq!(name_of(&[("WAYLAND_DISPLAY", "wayland-0"), ("PATH", &path)]), None);
+        assert_eq!(name_of(&[("TMUX", "/tmp/tmux"), ("DISPLAY", ":0")]).as_deref(), Some("OSC 52"));
+        // A remote session can't reach the local clipboard through a command.
+        let remote = name_of(&[("SSH_TTY", "/dev/pts/0"), ("DISPLAY", ":0"), ("PATH", &path)]);
+        assert_eq!(remote.as_deref(), Some("OSC 52"));
+        assert_eq!(name_of(&[("ANTE_CLIPBOARD", "wl-copy -p"), ("SSH_TTY", "/dev/pts/0")]).as_deref(), Some("wl-copy"));
+        assert!(detect(Some("  "), None, |_| None).is_none());
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // A clipboard which can't be read gives None, so that the internal register is pasted instead.
+        let mut clipboard = detect(Some("true --copy"), None, |_| None).unwrap();
+        assert_eq!(clipboard.get_name(), "true");
+        assert!(clipboard.paste().is_none());
+        let mut clipboard = detect(Some("true"), Some("false"), |_| None).unwrap();
+        assert!(clipboard.paste().is_none());
+    }
+
+    #[test]
+    fn editor_registers() {
+        use editor::register::{Copied, Registers};
+        use editor::selection::SelectionKind;
+
+        let copied = |text: &str, kind| Copied {
+            text: String::from(text),
+            kind,
+        };
+        let mut registers = Registers::new();
+        assert_eq!(registers.get(None), None);
+        registers.store(None, copied("one", SelectionKind::Char));
+        registers.store(Some('a'), copied("two\nrows", SelectionKind::Line));
+        // Deleted text doesn't replace what was copied.
+        registers.store_deleted(copied("deleted", SelectionKind::Char));
+        assert_eq!(registers.get(None), Some(&copied("two\nrows", SelectionKind::Line)));
+        assert_eq!(registers.get(Some('a')), Some(&copied("two\nrows", SelectionKind::Line)));
+        assert_eq!(registers.get(Some('b')), None);
+        assert_eq!(registers.get(Some('0')).unwrap().text, "deleted");
+        assert_eq!(registers.get(Some('1')).unwrap().text, "two\nrows");
+        assert_eq!(registers.get(Some('2')).unwrap().text, "one");
+        assert_eq!(registers.get(Some('3')), None);
+        // The history keeps the last ten.
+        for i in 0..12 {
+            registers.store_deleted(copied(&i.to_string(), SelectionKind::Char));
+        }
+        assert_eq!(registers.get(Some('0')).unwrap().text, "11");
+        assert_eq!(registers.get(Some('9')).unwrap().text, "2");
+        assert_eq!(registers.get(Some('a')).unwrap().text, "two\nrows");
+        assert!(Registers::is_valid_name('+') && Registers::is_valid_name('5') && !Registers::is_valid_name('A'));
+    }
+
+    #[test]
+    fn editor_paste() {
+        use terminal::event::{Event, Key};
+
+        let mut editor = editor_of("paste", "abc\ndef\n");
+        let undo = Event::CtrlKeyPressed(Key::Char('z'));
+        let first_row = Event::CtrlKeyPressed(Key::Home);
+        let down = Event::KeyPressed(Key::DownArrow);
+
+        // A charwise text goes into the row, after or before the cursor.
+        press(&mut editor, &[key('v'), Event::KeyPressed(Key::RightArrow), key('y'), key('p')]);
+        assert_eq!(rows_of(&editor), ["aabbc", "def"]);
+        press(&mut editor, &[undo.clone(), first_row.clone(), key('P')]);
+        assert_eq!(rows_of(&editor), ["ababc", "def"]);
+
+        // A linewise text goes below or above the row of the cursor.
+        press(&mut editor, &[undo.clone(), first_row.clone(), key('Y'), down.clone(), key('p')]);
+        assert_eq!(rows_of(&editor), ["abc", "def", "abc"]);
+        press(&mut editor, &[undo.clone(), first_row.clone(), down.clone(), key('P')]);
+        assert_eq!(rows_of(&editor), ["abc", "abc", "def"]);
+        press(&mut editor, &[undo, first_row, key('P')]);
+        assert_eq!(rows_of(&editor), ["abc", "abc", "def"]);
+    }
+
+    #[test]
+    fn editor_selection_ranges() {
+        use terminal::event::{Event, Key};
+        use text_buffer::{Position, Range};
+
+        let mut editor = editor_of("selection", "abcd\nx\n漢字ab\n");
+        let range = |start: (usize, usize), end: (usize, usize)| {
+            Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+        };
+        let undo = Event::CtrlKeyPressed(Key::Char('z'));
+        let first_row = Event::CtrlKeyPressed(Key::Home);
+        let down = Event::KeyPressed(Key::DownArrow);
+        let right = Event::KeyPressed(Key::RightArrow);
+        let select_block = [first_row.clone(), right.clone(), right.clone(), Event::CtrlKeyPressed(Key::Char('v'))];
+
+        // The chars from the anchor to the cursor are selected, the line break at the end of a row too.
+        press(&mut editor, &[key('v')]);
+        assert_eq!(editor.selection_ranges(), [range((0, 0), (1, 0))]);
+        editor.process_event(right.clone());
+        assert_eq!(editor.selection_ranges(), [range((0, 0), (2, 0))]);
+        editor.process_event(down.clone());
+        assert_eq!(editor.selection_ranges(), [range((0, 0), (0, 2))]);
+        // The whole rows, up to the end of the buffer.
+        press(&mut editor, &[key('V'), down.clone()]);
+        assert_eq!(editor.selection_ranges(), [range((0, 0), (4, 2))]);
+        press(&mut editor, &[Event::KeyPressed(Key::Esc), key('G'), Event::KeyPressed(Key::End), key('v')]);
+        press(&mut editor, &[Event::KeyPressed(Key::LeftArrow)]);
+        assert_eq!(editor.selection_ranges(), [range((3, 2), (4, 2))]);
+
+        // A block is cut at the columns of the screen: a short row gives an empty range,
+        // a wide char is only included if the block starts or ends on it.
+        press(&mut editor, &[Event::KeyPressed(Key::Esc)]);
+        press(&mut editor, &select_block);
+        press(&mut editor, &[down.clone(), down.clone()]);
+        assert_eq!(editor.selection_ranges(), [range((0, 0), (3, 0)), range((0, 1), (1, 1)), range((0, 2), (1, 2))]);
+        editor.process_event(right.clone());
+        assert_eq!(editor.selection_ranges(), [range((2, 0), (4, 0)), range((1, 1), (1, 1)), range((1, 2), (2, 2))]);
+
+        // Copying, cutting and deleting act on these ranges.
+        press(&mut editor, &[key('y'), key('P')]);
+        assert_eq!(rows_of(&editor), ["abcdcd", "x ", "漢字字ab"]);
+        editor.process_event(undo.clone());
+        press(&mut editor, &select_block);
+        press(&mut editor, &[down.clone(), down.clone(), right.clone(), key('x')]);
+        assert_eq!(rows_of(&editor), ["ab", "x", "漢ab"]);
+        editor.process_event(undo.clone());
+        assert_eq!(rows_of(&editor), ["abcd", "x", "漢字ab"]);
+        // Deleting doesn't replace the block cut before.
+        press(&mut editor, &[first_row, key('v'), right, key('d'), key('P')]);
+        assert_eq!(rows_of(&editor), ["cdcd", "x", "字漢字ab"]);
+    }
+
+    #[test]
+    fn editor_swap_file() {
+        use editor::swap::{find_stale_swap_file, read_swap_file, SwapFile, SwapOwner};
+
+        let directory = std::env::temp_dir();
+        let path = directory.join(format!("ante-test-swap-{}.txt", std::process::id()));
+        let swap_path = directory.join(format!(".ante-test-swap-{}.txt.ante-swp", std::process::id()));
+        std::fs::write(&path, "saved\n").unwrap();
+        let mut buffer = text_buffer::Buffer::new_from_file(path.clone()).unwrap();
+        let mut swap_file = SwapFile::new();
+        assert!(!swap_file.is_outdated(&buffer));
+        buffer.insert_char(0, 0, 'x');
+        assert!(swap_file.is_outdated(&buffer));
+        swap_file.write(&buffer).unwrap();
+        assert!(!swap_file.is_outdated(&buffer));
+
+        let content = read_swap_file(&swap_path).unwrap();
+        assert_eq!(content.file_path, Some(path.clone()));
+        assert_eq!(content.owner, Some(SwapOwner::current()));
+        assert_eq!(content.text, "xsaved\n");
+        // The swap file of a running session isn't stale.
+        assert_eq!(find_stale_swap_file(Some(&path)), None);
+        swap_file.remove();
+        assert!(!swap_path.exists());
+
+        // Once its process is gone, or without owner (older versions), it is.
+        let owner = SwapOwner::current();
+        std::fs::write(&swap_path, format!("ante swap file v2\n\n{} {}\nlost\n", i32::MAX, owner.hostname)).unwrap();
+        assert_eq!(find_stale_swap_file(Some(&path)), Some(swap_path.clone()));
+        assert_eq!(read_swap_file(&swap_path).unwrap().text, "lost\n");
+        std::fs::write(&swap_path, "ante swap file\n\nlost\n").unwrap();
+        assert_eq!(find_stale_swap_file(Some(&path)), Some(swap_path.clone()));
+        assert_eq!(read_swap_file(&swap_path).unwrap().owner, None);
+        std::fs::write(&swap_path, "something else\n").unwrap();
+        assert_eq!(find_stale_swap_file(Some(&path)), None);
+
+        std::fs::remove_file(&swap_path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn editor_swap_file_sharing() {
+        use editor::swap::{read_swap_file, SwapFile, SwapOwner};
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = std::env::temp_dir().join(format!("ante-test-swap-sharing-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("secret");
+        let swap_path = directory.join(".secret.ante-swp");
+        std::fs::write(&path, "saved\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let mut buffer = text_buffer::Buffer::new_from_file(path.clone()).unwrap();
+        buffer.insert_char(0, 0, 'x');
+
+        // The swap file is only readable by its owner, even over an older one.
+        std::fs::write(&swap_path, "ante swap file\n\nlost\n").unwrap();
+        std::fs::set_permissions(&swap_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let mut swap_file = SwapFile::new();
+        swap_file.write(&buffer).unwrap();
+        assert_eq!(read_swap_file(&swap_path).unwrap().text, "xsaved\n");
+        assert_eq!(std::fs::metadata(&swap_path).unwrap().permissions().mode() & 0o777, 0o600);
+        swap_file.remove();
+
+        // The swap file of another running session (the process 1 is always running) isn't written over.
+        let other = format!("ante swap file v2\n{}\n1 {}\nother\n", path.display(), SwapOwner::current().hostname);
+        std::fs::write(&swap_path, &other).unwrap();
+        let mut swap_file = SwapFile::new();
+        if swap_file.write(&buffer).is_ok() {
+            swap_file.remove();
+        }
+        assert_eq!(std::fs::read_to_string(&swap_path).unwrap(), other);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn editor_diff_lines() {
+        use editor::diff::{diff_lines, DiffLine};
+
+        assert_eq!(
+            diff_lines("a\nb\nc\nd\n", "a\nx\nc\nd\ne\n"),
+            vec![
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("x"),
+                DiffLine::Same("c"),
+                DiffLine::Same("d"),
+                DiffLine::Added("e"),
+            ]
+        );
+        assert_eq!(diff_lines("same\n", "same\n"), vec![DiffLine::Same("same")]);
+        assert_eq!(diff_lines("", "new"), vec![DiffLine::Added("new")]);
+        assert_eq!(
+            diff_lines("1\n2\n3", "3\n1"),
+            vec![DiffLine::Removed("1"), DiffLine::Removed("2"), DiffLine::Same("3"), DiffLine::Added("1")]
+        );
+    }
+
+    #[test]
+    fn text_buffer_search() {
+        use text_buffer::{SearchDirection, SearchMatch, SearchPattern};
+
+        let buffer = text_buffer::Buffer::new_from_str("été Foo\nfoo bar\nbaz");
+        let literal = SearchPattern::new("foo", false, false).unwrap();
+        let found = buffer.search(&literal, 0, 1, SearchDirection::Forward);
+        assert_eq!(found, Some(SearchMatch { row: 0, start_col: 4, end_col: 7 }));
+        let found = buffer.search(&literal, 4, 0, SearchDirection::Forward);
+        assert_eq!(found, Some(SearchMatch { row: 1, start_col: 0, end_col: 3 }));
+        let found = buffer.search(&literal, 0, 1, SearchDirection::Backward);
+        assert_eq!(found.map(|found| found.row), Some(0));
+
+        let case_sensitive = SearchPattern::new("Foo", false, true).unwrap();
+        assert_eq!(buffer.find_all(&case_sensitive).len(), 1);
+        let regex = SearchPattern::new("ba[rz]", true, false).unwrap();
+        assert_eq!(buffer.find_all(&regex).len(), 2);
+        assert!(SearchPattern::new("a.b", false, false).unwrap().get_regex().find("axb").is_none());
+        assert!(SearchPattern::new("(", true, false).is_err());
+    }
+
+    #[test]
+    fn text_buffer_replace() {
+        use text_buffer::SearchPattern;
+
+        let mut buffer = text_buffer::Buffer::new_from_str("key=value\nname=ante x=y");
+        let pattern = SearchPattern::new("(\\w+)=(\\w+)", true, false).unwrap();
+        buffer.commit_transaction();
+        assert_eq!(buffer.replace_all_from(&pattern, "$2: $1", 0, 0), 3);
+        buffer.commit_transaction();
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "value: key");
+        assert_eq!(buffer.borrow_row_at(1).to_string(), "ante: name y: x");
+
+        buffer.undo();
+        assert_eq!(buffer.borrow_row_at(1).to_string(), "name=ante x=y");
+        let literal = SearchPattern::new("x", false, false).unwrap();
+        let found = buffer.find_from(&literal, 0, 0).unwrap();
+        assert_eq!(buffer.replace_match(&literal, &found, "$1"), (12, 1));
+        assert_eq!(buffer.borrow_row_at(1).to_string(), "name=ante $1=y");
+
+        // An anchored pattern matches the text as it was, not the text left by a replacement.
+        let mut buffer = text_buffer::Buffer::new_from_str("   a\n\tb\nc");
+        let leading_space = SearchPattern::new("^\\s", true, false).unwrap();
+        assert_eq!(buffer.replace_all_from(&leading_space, "", 0, 0), 2);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "  a");
+        assert_eq!(buffer.borrow_row_at(1).to_string(), "b");
+
+        // Replacing the matches of a row one by one, as the confirmation does.
+        let mut buffer = text_buffer::Buffer::new_from_str("a-b a-b");
+        let pair = SearchPattern::new("(\\w)-(\\w)", true, false).unwrap();
+        let (row_text, matches) = buffer.find_in_row(&pair, 0);
+        let shift = buffer.replace_row_matches(&pair, &row_text, &matches[..1], 0, "$2--$1");
+        assert_eq!(shift, 1);
+        buffer.replace_row_matches(&pair, &row_text, &matches[1..], shift, "$2$1");
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "b--a ba");
+    }
+
+    #[test]
+    fn text_buffer_graphemes() {
+        // "e" followed by a combining acute accent, a family emoji (ZWJ sequence), two wide CJK characters.
+        let buffer = text_buffer::Buffer::new_from_str("e\u{301}\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}日本\nabcdefgh");
+        assert_eq!(buffer.next_grapheme_col(0, 0), 2);
+        assert_eq!(buffer.next_grapheme_col(2, 0), 7);
+        assert_eq!(buffer.next_grapheme_col(9, 0), 9);
+        assert_eq!(buffer.previous_grapheme_col(7, 0), 2);
+        assert_eq!(buffer.previous_grapheme_col(2, 0), 0);
+        assert_eq!(buffer.display_col_of(2, 0, 4), 1);
+        assert_eq!(buffer.display_col_of(7, 0, 4), 3);
+        assert_eq!(buffer.display_col_of(9, 0, 4), 7);
+        // The middle of a wide character gives the column of that character.
+        assert_eq!(buffer.col_at_display_col(4, 0, 4), 7);
+        assert_eq!(buffer.col_at_display_col(5, 0, 4), 8);
+        assert_eq!(buffer.col_at_display_col(5, 1, 4), 5);
+        assert_eq!(buffer.col_at_display_col(20, 1, 4), 8);
+
+        // A tab spans the cells up to the next tab stop.
+        let buffer = text_buffer::Buffer::new_from_str("ab\tc\t\td");
+        assert_eq!(buffer.display_col_of(3, 0, 4), 4);
+        assert_eq!(buffer.display_col_of(6, 0, 4), 12);
+        assert_eq!(buffer.display_col_of(3, 0, 8), 8);
+        assert_eq!(buffer.col_at_display_col(3, 0, 4), 2);
+        assert_eq!(buffer.col_at_display_col(9, 0, 4), 5);
+
+        // The other control characters are drawn in caret notation, a lone '\r' being kept into its row.
+        let buffer = text_buffer::Buffer::new_from_str("a\rb\u{1b}c\u{7f}\u{85}d\r\n");
+        assert_eq!(buffer.get_lenght(), 1);
+        assert_eq!(text_buffer::control_notation("\r").as_deref(), Some("^M"));
+        assert_eq!(text_buffer::control_notation("\u{1b}").as_deref(), Some("^["));
+        assert_eq!(text_buffer::control_notation("\u{7f}").as_deref(), Some("^?"));
+        assert_eq!(text_buffer::control_notation("\u{85}").as_deref(), Some("<85>"));
+        assert_eq!(text_buffer::control_notation("\t"), None);
+        assert_eq!(text_buffer::control_notation("a"), None);
+        assert_eq!(buffer.display_col_of(2, 0, 4), 3);
+        assert_eq!(buffer.display_col_of(8, 0, 4), 14);
+        assert_eq!(buffer.col_at_display_col(2, 0, 4), 1);
+        assert_eq!(buffer.col_at_display_col(3, 0, 4), 2);
+    }
+
+    #[test]
+    fn text_buffer_ranges() {
+        use text_buffer::{Position, Range};
+
+        let mut buffer = text_buffer::Buffer::new_from_str("first\nsecond\nthird");
+        let end = buffer.insert_str(Position::new(2, 0), "AB\r\nCD\nE");
+        assert_eq!(end, Position::new(1, 2));
+        assert_eq!(buffer.get_lenght(), 5);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "fiAB");
+        assert_eq!(buffer.borrow_row_at(2).to_string(), "Erst");
+
+        // The positions may be given in any order, the line breaks in between are joined.
+        let range = Range::new(Position::new(3, 3), Position::new(3, 0));
+        assert_eq!(buffer.slice(range), "B\nCD\nErst\nsec");
+        assert_eq!(buffer.delete_range(range), "B\nCD\nErst\nsec");
+        assert_eq!(buffer.get_lenght(), 2);
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "fiAond");
+
+        // Positions past the end of a row or of the buffer are moved back to it.
+        buffer.commit_transaction();
+        let range = Range::new(Position::new(99, 0), Position::new(99, 99));
+        assert_eq!(buffer.replace_range(range, "!"), Position::new(7, 0));
+        assert_eq!(buffer.get_lenght(), 1);
+        assert_eq!(buffer.end_position(), Position::new(7, 0));
+        assert!(buffer.has_unsaved_changes());
+        assert_eq!(buffer.undo(), Some((6, 0)));
+        assert_eq!(buffer.get_lenght(), 2);
+    }
+
+    #[test]
+    fn text_buffer_edit_events() {
+        use text_buffer::{Position, Range};
+
+        let mut buffer = text_buffer::Buffer::new_from_str("one\ntwo\nthree");
+        let observer = buffer.observe_edits();
+        buffer.stop_observing_edits(observer);
+        buffer.insert_char(0, 0, 'x');
+        assert!(buffer.take_edits(observer).is_empty());
+        buffer.commit_transaction();
+
+        let observer = buffer.observe_edits();
+        buffer.replace_range(Range::new(Position::new(2, 0), Position::new(1, 1)), "A\nB\nC");
+        let edits = buffer.take_edits(observer);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].removed, Range::new(Position::new(2, 0), Position::new(1, 1)));
+        assert_eq!(edits[0].removed_text, "ne\nt");
+        assert_eq!(edits[0].inserted, Range::new(Position::new(2, 0), Position::new(1, 2)));
+        assert_eq!(edits[0].changed_rows(), 0..=2);
+        assert_eq!(edits[0].row_shift(), 1);
+
+        // Undoing emits the reverse edit.
+        buffer.undo();
+        let edits = buffer.take_edits(observer);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].removed_text, "A\nB\nC");
+        assert_eq!(edits[0].row_shift(), -1);
+        assert!(buffer.take_edits(observer).is_empty());
+
+        // Every observer gets every edit, whatever the others do.
+        let other = buffer.observe_edits();
+        buffer.insert_char(0, 0, 'y');
+        assert_eq!(buffer.take_edits(other).len(), 1);
+        buffer.insert_char(0, 0, 'z');
+        let edits = buffer.take_edits(observer);
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[1].inserted_text, "z");
+        buffer.stop_observing_edits(observer);
+        buffer.insert_char(0, 0, 'w');
+        let edits = buffer.take_edits(other);
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].inserted_text, "z");
+        assert!(buffer.take_edits(observer).is_empty());
+    }
+
+    #[test]
+    fn text_buffer_undo_group() {
+        let mut buffer = text_buffer::Buffer::new_from_str("ab");
+        buffer.begin_undo_group();
+        buffer.insert_char(0, 0, 'x');
+        buffer.commit_transaction();
+        buffer.insert_char(3, 0, 'y');
+        buffer.commit_transaction();
+        buffer.end_undo_group();
+        buffer.insert_char(0, 0, 'z');
+
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "zxaby");
+        buffer.undo();
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "xaby");
+        buffer.undo();
+        assert_eq!(buffer.borrow_row_at(0).to_string(), "ab");
+    }
+
+    #[test]
+    fn event_notation() {
+        use terminal::event::{parse_notation, Event, Key};
+
+        let events = vec![
+            Event::KeyPressed(Key::Char('e')),
+            Event::KeyPressed(Key::Char('<')),
+            Event::KeyPressed(Key::Enter),
+            Event::CtrlKeyPressed(Key::Char('s')),
+            Event::CtrlKeyPressed(Key::Char('>')),
+            Event::CtrlKeyPressed(Key::DownArrow),
+            Event::KeyPressed(Key::Esc),
+        ];
+        let notation: String = events.iter().filter_map(Event::to_notation).collect();
+        assert_eq!(notation, "e<lt><CR><C-s><C-gt><C-Down><Esc>");
+        assert_eq!(parse_notation(&notation), Some(events));
+        assert_eq!(Event::WindowResized(80, 24).to_notation(), None);
+        assert_eq!(parse_notation("a<Nope>"), None);
+        assert_eq!(parse_notation("<C-"), None);
+    }
+
+    #[test]
+    fn event_modifiers() {
+        use terminal::event::{parse_notation, Event, Key, Modifiers};
+
+        let alt = Modifiers { alt: true, ..Modifiers::default() };
+        let ctrl_shift = Modifiers { ctrl: true, shift: true, ..Modifiers::default() };
+        let shift = Modifiers { shift: true, ..Modifiers::default() };
+        // Shift is part of the chars and of BackTab, and alone it doesn't change the other keys.
+        assert_eq!(Event::key_pressed(Key::Char('A'), shift), Event::KeyPressed(Key::Char('A')));
+        assert_eq!(Event::key_pressed(Key::Tab, shift), Event::KeyPressed(Key::BackTab));
+        assert_eq!(Event::key_pressed(Key::Home, shift), Event::KeyPressed(Key::Home));
+        assert_eq!(Event::key_pressed(Key::Char('s'), ctrl_shift), Event::CtrlKeyPressed(Key::Char('s')));
+        assert_eq!(Event::key_pressed(Key::UpArrow, ctrl_shift), Event::ModifiedKeyPressed(Key::UpArrow, ctrl_shift));
+
+        let events = vec![
+            Event::ModifiedKeyPressed(Key::Char('x'), alt),
+            Event::ModifiedKeyPressed(Key::UpArrow, ctrl_shift),
+            Event::KeyPressed(Key::BackTab),
+            Event::CtrlKeyPressed(Key::Home),
+            Event::KeyPressed(Key::PageDown),
+            Event::KeyPressed(Key::Delete),
+            Event::KeyPressed(Key::Insert),
+            Event::KeyPressed(Key::F(12)),
+        ];
+        let notation: String = events.iter().filter_map(Event::to_notation).collect();
+        assert_eq!(notation, "<A-x><C-S-Up><S-Tab><C-Home><PageDown><Del><Insert><F12>");
+        assert_eq!(parse_notation(&notation), Some(events));
+        assert_eq!(parse_notation("<M-x><C-->"), parse_notation("<A-x><C-->"));
+        assert_eq!(parse_notation("<C-->").unwrap()[0], Event::CtrlKeyPressed(Key::Char('-')));
+        assert_eq!(parse_notation("<S-a>"), None);
+        assert_eq!(parse_notation("<F0>"), None);
+    }
+
+    #[test]
+    fn event_mouse() {
+        use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+        use terminal::event::{mouse_event, Event};
+
+        let mouse = |kind| MouseEvent {
+            kind,
+            column: 12,
+            row: 3,
+            modifiers: KeyModifiers::NONE,
+        };
+        assert_eq!(mouse_event(mouse(MouseEventKind::Down(MouseButton::Left))), Event::MouseClicked(12, 3));
+        assert_eq!(mouse_event(mouse(MouseEventKind::Drag(MouseButton::Left))), Event::MouseDragged(12, 3));
+        assert_eq!(mouse_event(mouse(MouseEventKind::ScrollDown)), Event::MouseScrolledDown);
+        assert_eq!(mouse_event(mouse(MouseEventKind::Down(MouseButton::Right))), Event::Unknown);
+        assert_eq!(mouse_event(mouse(MouseEventKind::Up(MouseButton::Left))), Event::Unknown);
+        assert_eq!(Event::MouseClicked(12, 3).to_notation(), None);
+    }
+
+    #[test]
+    fn editor_keymap() {
+        use editor::{Action, Keymap, KeymapMode, Layout, Motion};
+        use terminal::event::{Event, Key};
+
+        assert_eq!(Layout::from_name("QWERTY"), Some(Layout::Qwerty));
+        assert_eq!(Layout::from_name("dvorak"), None);
+        for name in Layout::NAMES {
+            assert_eq!(Layout::from_name(name).map(|layout| layout.get_name().to_string()), Some(name.to_string()));
+        }
+        let motion_of = |layout: Layout, key: Key| {
+            match Keymap::new(layout).get(KeymapMode::Navigation, &[Event::KeyPressed(key)]) {
+                Some(Action::Move(motion)) => Some(motion),
+                _ => None,
+            }
+        };
+        assert_eq!(motion_of(Layout::Bepo, Key::Char('t')), Some(Motion::Down));
+        assert_eq!(motion_of(Layout::Qwerty, Key::Char('h')), Some(Motion::Left));
+        assert_eq!(motion_of(Layout::Azerty, Key::Char('m')), Some(Motion::Right));
+        assert_eq!(motion_of(Layout::Qwerty, Key::Char('t')), None);
+        assert_eq!(motion_of(Layout::ArrowsOnly, Key::Char('k')), None);
+        assert_eq!(motion_of(Layout::ArrowsOnly, Key::UpArrow), Some(Motion::Up));
+
+        // A mode binding wins over the global one, and can be changed or removed.
+        let mut keymap = Keymap::new(Layout::Bepo);
+        let ctrl_v = [Event::CtrlKeyPressed(Key::Char('v'))];
+        assert_eq!(keymap.get(KeymapMode::Edition, &ctrl_v), Some(Action::PasteAtCursors));
+        assert_eq!(keymap.get(KeymapMode::Navigation, &ctrl_v), Some(Action::SelectBlock));
+        keymap.bind(KeymapMode::Navigation, vec![Event::KeyPressed(Key::Char('e'))], Some(Action::Save));
+        keymap.bind(KeymapMode::Global, vec![Event::CtrlKeyPressed(Key::Char('s'))], None);
+        assert_eq!(keymap.get(KeymapMode::Navigation, &[Event::KeyPressed(Key::Char('e'))]), Some(Action::Save));
+        assert_eq!(keymap.get(KeymapMode::Selection, &[Event::CtrlKeyPressed(Key::Char('s'))]), None);
+        assert_eq!(Action::from_name(Action::CutRows.get_name()), Some(Action::CutRows));
+
+        let (config, errors) = config::Config::parse(
+            "[keys.navigation]\n\"<C-f>\" = \"search-forward\"\nx = \"none\"\n\
+             \"<Nope>\" = \"save\"\ny = \"fly\"\n[keys.insert]\nz = \"undo\"\n",
+        );
+        assert_eq!(config.key_bindings.len(), 2);
+        assert_eq!(
+            config.key_bindings[0],
+            (KeymapMode::Navigation, vec![Event::CtrlKeyPressed(Key::Char('f'))], Some(Action::SearchForward))
+        );
+        let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "4:1: invalid key `<Nope>`",
+                "5:5: unknown action `fly`",
+                "7:1: unknown mode `insert` (global, navigation, edition or selection)",
+            ]
+        );
+    }
+
+    #[test]
+    fn editor_key_sequences() {
+        use editor::{Action, Keymap, KeymapMode, Layout, Motion, Resolution};
+        use terminal::event::parse_notation;
+
+        let mut keymap = Keymap::new(Layout::Qwerty);
+        let resolve = |keymap: &Keymap, mode, keys: &str| keymap.resolve(mode, &parse_notation(keys).unwrap());
+        assert_eq!(resolve(&keymap, KeymapMode::Navigation, "g"), Resolution::Prefix(None));
+        assert_eq!(resolve(&keymap, KeymapMode::Navigation, "gg"), Resolution::Action(Action::GoToFirstRow));
+        assert_eq!(resolve(&keymap, KeymapMode::Navigation, "gx"), Resolution::None);
+        assert_eq!(resolve(&keymap, KeymapMode::Navigation, "j"), Resolution::Action(Action::Move(Motion::Down)));
+        // The global sequences are reached from every mode.
+        assert_eq!(resolve(&keymap, KeymapMode::Edition, "<C-x>"), Resolution::Prefix(None));
+        assert_eq!(resolve(&keymap, KeymapMode::Edition, "<C-x><C-s>"), Resolution::Action(Action::Save));
+        // A bound key starting a longer sequence waits for the next key.
+        keymap.bind(KeymapMode::Edition, parse_notation("jk").unwrap(), Some(Action::NavigationMode));
+        keymap.bind(KeymapMode::Edition, parse_notation("j").unwrap(), Some(Action::NewLine));
+        assert_eq!(resolve(&keymap, KeymapMode::Edition, "j"), Resolution::Prefix(Some(Action::NewLine)));
+        assert_eq!(resolve(&keymap, KeymapMode::Edition, "jk"), Resolution::Action(Action::NavigationMode));
+        assert_eq!(resolve(&keymap, KeymapMode::Navigation, "jk"), Resolution::None);
+
+        let (config, errors) =
+            config::Config::parse("[keys]\ntimeout = 300\n[keys.edition]\njk = \"navigation-mode\"\n");
+        assert!(errors.is_empty());
+        assert_eq!(config.key_timeout, std::time::Duration::from_millis(300));
+        assert_eq!(config.key_bindings[0].1, parse_notation("jk").unwrap());
+        let (_, errors) = config::Config::parse("[keys]\ntimeout = -1\n");
+        assert_eq!(errors[0].to_string(), "2:11: the timeout must be between 0 and 10000 ms");
+    }
+
+    #[test]
+    fn config_parsing() {
+        use config::parser::ParseError;
+        use config::Config;
+
+        let (config, errors) = Config::parse(
+            "# comment\ntab_width = 8\nexpand_tab = true  # spaces\n\n[keys]\nlayout = \"qwerty\"\n\
+             [status_bar]\nposition = true\n[save]\ntrim_trailing_whitespace = true\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(config.tab_width, 8);
+        assert!(config.expand_tab);
+        assert_eq!(config.layout, editor::Layout::Qwerty);
+        assert!(config.show_position && config.show_file_format);
+        assert!(config.trim_trailing_whitespace && config.swap_file);
+
+        let (config, errors) = Config::parse(
+            "tab_width = 0\nexpand_tab = \"yes\"\n[colors]\nselection_background = \"purple\"\n\
+             status_bar_foreground = \"#ff8000\"\nfoo = 1\n[keys\nlayout = qwerty\n",
+        );
+        assert_eq!(config.tab_width, 4);
+        assert!(!config.expand_tab);
+        assert_eq!(
+            errors,
+            vec![
+                ParseError::new(1, 13, "the tab width must be between 1 and 16"),
+                ParseError::new(2, 14, "expected a boolean, found a string"),
+                ParseError::new(4, 24, "unknown color `purple`"),
+                ParseError::new(6, 1, "unknown setting `colors.foo`"),
+                ParseError::new(7, 6, "expected `[section]`"),
+                ParseError::new(8, 10, "invalid value `qwerty` (strings are written between quotes)"),
+            ]
+        );
+        assert_eq!(errors[0].to_string(), "1:13: the tab width must be between 1 and 16");
+    }
+
     #[test]
     fn init_terminal() {
         let _terminal = terminal::Terminal::new();
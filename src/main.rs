@@ -1,20 +1,48 @@
+use ante::config::Config;
+use ante::editor::Layout;
 use ante::Editor;
 use std::env::args;
 use std::process::exit;
 
+// ante [--layout bepo|qwerty|azerty|arrows] [path]
 fn main() {
-    let path_arg = args().nth(1);
+    let mut path_arg = None;
+    let mut layout = None;
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        let layout_name = match arg.strip_prefix("--layout") {
+            Some("") => args.next(),
+            Some(name) if name.starts_with('=') => Some(name[1..].to_string()),
+            _ => {
+                path_arg = Some(arg);
+                continue;
+            }
+        };
+        match layout_name.as_deref().and_then(Layout::from_name) {
+            Some(name) => layout = Some(name),
+            None => {
+                println!("Unknown layout, expected one of: {}", Layout::NAMES.join(", "));
+                exit(1);
+            }
+        }
+    }
     if let Some(s) = &path_arg {
         for character in s.chars() {
             match character {
                 '<' | '>' | ':' | '\"' | '|' | '?' | '*' => {
-                    println!("Invalid character: {}", character);
+                    println!("Invalid character: {}", character);
                     exit(0);
                 }
                 _ => (),
             }
         }
     }
-    let mut editor = Editor::new(path_arg);
+    // The command line wins over the configuration file.
+    let (mut config, config_errors) = Config::load();
+    if let Some(layout) = layout {
+        config.layout = layout;
+    }
+    let mut editor = Editor::new(path_arg, config);
+    editor.report_config_errors(&config_errors);
     editor.run();
 }
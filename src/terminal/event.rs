@@ -1,12 +1,21 @@
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 
+#[derive(Clone, PartialEq, Debug)]
 pub enum Event {
     KeyPressed(Key),
     CtrlKeyPressed(Key),
+    // A key pressed with Alt, or with Shift and Ctrl or Alt (see `Event::key_pressed`).
+    ModifiedKeyPressed(Key, Modifiers),
+    // Left button pressed, or moved while pressed, at a cell of the screen (column, row).
+    MouseClicked(usize, usize),
+    MouseDragged(usize, usize),
+    MouseScrolledUp,
+    MouseScrolledDown,
     WindowResized(usize, usize),
     Unknown,
 }
 
+#[derive(Clone, PartialEq, Debug)]
 pub enum Key {
     Char(char),
     Backspace,
@@ -16,34 +25,191 @@ pub enum Key {
     UpArrow,
     DownArrow,
     Tab,
+    // Shift + Tab.
+    BackTab,
     Esc,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Delete,
+    Insert,
+    F(u8),
 }
 
-pub fn single_key_pressed(key_code: KeyCode) -> Event {
-    match key_code {
-        KeyCode::Char(c) => Event::KeyPressed(Key::Char(c)),
-        KeyCode::Backspace => Event::KeyPressed(Key::Backspace),
-        KeyCode::Enter => Event::KeyPressed(Key::Enter),
-        KeyCode::Left => Event::KeyPressed(Key::LeftArrow),
-        KeyCode::Right => Event::KeyPressed(Key::RightArrow),
-        KeyCode::Up => Event::KeyPressed(Key::UpArrow),
-        KeyCode::Down => Event::KeyPressed(Key::DownArrow),
-        KeyCode::Tab => Event::KeyPressed(Key::Tab),
-        KeyCode::Esc => Event::KeyPressed(Key::Esc),
-        _ => Event::Unknown,
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Modifiers {
+    pub fn from_crossterm(modifiers: KeyModifiers) -> Self {
+        Self {
+            ctrl: modifiers.contains(KeyModifiers::CONTROL),
+            alt: modifiers.contains(KeyModifiers::ALT),
+            shift: modifiers.contains(KeyModifiers::SHIFT),
+        }
     }
 }
-pub fn key_pressed_with_control(key_code: KeyCode) -> Event {
-    match key_code {
-        KeyCode::Char(c) => Event::CtrlKeyPressed(Key::Char(c)),
-        KeyCode::Backspace => Event::CtrlKeyPressed(Key::Backspace),
-        KeyCode::Enter => Event::CtrlKeyPressed(Key::Enter),
-        KeyCode::Left => Event::CtrlKeyPressed(Key::LeftArrow),
-        KeyCode::Right => Event::CtrlKeyPressed(Key::RightArrow),
-        KeyCode::Up => Event::CtrlKeyPressed(Key::UpArrow),
-        KeyCode::Down => Event::CtrlKeyPressed(Key::DownArrow),
-        KeyCode::Tab => Event::CtrlKeyPressed(Key::Tab),
-        KeyCode::Esc => Event::CtrlKeyPressed(Key::Esc),
+
+pub fn key_of_code(key_code: KeyCode) -> Option<Key> {
+    let key = match key_code {
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Left => Key::LeftArrow,
+        KeyCode::Right => Key::RightArrow,
+        KeyCode::Up => Key::UpArrow,
+        KeyCode::Down => Key::DownArrow,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::BackTab => Key::BackTab,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::F(number) => Key::F(number),
+        _ => return None,
+    };
+    Some(key)
+}
+
+// Only the left button and the wheel are used.
+pub fn mouse_event(mouse_event: MouseEvent) -> Event {
+    let (col, row) = (mouse_event.column as usize, mouse_event.row as usize);
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) => Event::MouseClicked(col, row),
+        MouseEventKind::Drag(MouseButton::Left) => Event::MouseDragged(col, row),
+        MouseEventKind::ScrollUp => Event::MouseScrolledUp,
+        MouseEventKind::ScrollDown => Event::MouseScrolledDown,
         _ => Event::Unknown,
     }
 }
+
+/* Key presses written as text, to store them (macros): a char stands for itself, other keys are named
+between angle brackets, like <CR> or <Left>, and the modifiers are written C- (control), A- (alt, M- is read too)
+and S- (shift), like <C-s> or <A-S-Up>. '<' is written <lt>. */
+impl Event {
+    /* The event of a key pressed with modifiers, the same whatever the terminal sends: Shift is part of the chars
+    ('A') and of BackTab, and is ignored with the other keys unless Ctrl or Alt is pressed too. */
+    pub fn key_pressed(key: Key, modifiers: Modifiers) -> Self {
+        let key = match key {
+            Key::Tab if modifiers.shift => Key::BackTab,
+            key => key,
+        };
+        let shift = modifiers.shift && !matches!(key, Key::Char(_) | Key::BackTab);
+        match modifiers {
+            Modifiers { ctrl: false, alt: false, .. } => Event::KeyPressed(key),
+            Modifiers { ctrl: true, alt: false, .. } if !shift => Event::CtrlKeyPressed(key),
+            Modifiers { ctrl, alt, .. } => Event::ModifiedKeyPressed(key, Modifiers { ctrl, alt, shift }),
+        }
+    }
+    // The key pressed, with its modifiers. None for the events which aren't key presses.
+    pub fn get_key(&self) -> Option<(&Key, Modifiers)> {
+        match self {
+            Event::KeyPressed(key) => Some((key, Modifiers::default())),
+            Event::CtrlKeyPressed(key) => Some((key, Modifiers { ctrl: true, ..Modifiers::default() })),
+            Event::ModifiedKeyPressed(key, modifiers) => Some((key, *modifiers)),
+            _ => None,
+        }
+    }
+
+    // None for the events which aren't key presses.
+    pub fn to_notation(&self) -> Option<String> {
+        let (key, modifiers) = self.get_key()?;
+        let modified = modifiers.ctrl || modifiers.alt;
+        let name = match key {
+            Key::Char('<') => String::from("lt"),
+            Key::Char('>') if modified => String::from("gt"),
+            Key::Char(c) if !modified => return Some(c.to_string()),
+            Key::Char(c) => c.to_string(),
+            Key::Backspace => String::from("BS"),
+            Key::Enter => String::from("CR"),
+            Key::LeftArrow => String::from("Left"),
+            Key::RightArrow => String::from("Right"),
+            Key::UpArrow => String::from("Up"),
+            Key::DownArrow => String::from("Down"),
+            Key::Tab => String::from("Tab"),
+            Key::BackTab => String::from("S-Tab"),
+            Key::Esc => String::from("Esc"),
+            Key::Home => String::from("Home"),
+            Key::End => String::from("End"),
+            Key::PageUp => String::from("PageUp"),
+            Key::PageDown => String::from("PageDown"),
+            Key::Delete => String::from("Del"),
+            Key::Insert => String::from("Insert"),
+            Key::F(number) => format!("F{}", number),
+        };
+        Some(format!(
+            "<{}{}{}{}>",
+            if modifiers.ctrl { "C-" } else { "" },
+            if modifiers.alt { "A-" } else { "" },
+            if modifiers.shift { "S-" } else { "" },
+            name
+        ))
+    }
+}
+
+// Reads key presses written by `Event::to_notation`. None if a name between angle brackets is unknown.
+pub fn parse_notation(text: &str) -> Option<Vec<Event>> {
+    let mut events = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            events.push(Event::KeyPressed(Key::Char(c)));
+            continue;
+        }
+        let name: String = chars.by_ref().take_while(|c| *c != '>').collect();
+        let mut name = name.as_str();
+        let mut modifiers = Modifiers::default();
+        // A single char may follow the modifiers, '-' included.
+        while name.chars().count() > 2 {
+            if let Some(rest) = name.strip_prefix("C-") {
+                modifiers.ctrl = true;
+                name = rest;
+            } else if let Some(rest) = name.strip_prefix("A-").or_else(|| name.strip_prefix("M-")) {
+                modifiers.alt = true;
+                name = rest;
+            } else if let Some(rest) = name.strip_prefix("S-") {
+                modifiers.shift = true;
+                name = rest;
+            } else {
+                break;
+            }
+        }
+        let key = match name {
+            "lt" => Key::Char('<'),
+            "gt" => Key::Char('>'),
+            "BS" => Key::Backspace,
+            "CR" => Key::Enter,
+            "Left" => Key::LeftArrow,
+            "Right" => Key::RightArrow,
+            "Up" => Key::UpArrow,
+            "Down" => Key::DownArrow,
+            "Tab" => Key::Tab,
+            "Esc" => Key::Esc,
+            "Home" => Key::Home,
+            "End" => Key::End,
+            "PageUp" => Key::PageUp,
+            "PageDown" => Key::PageDown,
+            "Del" => Key::Delete,
+            "Insert" => Key::Insert,
+            _ => match name.strip_prefix('F').map(str::parse::<u8>) {
+                Some(Ok(number)) if number > 0 => Key::F(number),
+                _ => {
+                    let mut name_chars = name.chars();
+                    match (name_chars.next(), name_chars.next()) {
+                        (Some(c), None) if modifiers.ctrl || modifiers.alt => Key::Char(c),
+                        _ => return None,
+                    }
+                }
+            },
+        };
+        events.push(Event::key_pressed(key, modifiers));
+    }
+    Some(events)
+}
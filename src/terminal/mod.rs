@@ -1,7 +1,6 @@
 use crossterm::event::Event as CrosstermEvent;
-use crossterm::event::{KeyCode, KeyModifiers};
 use crossterm::{terminal, Command, ExecutableCommand, QueueableCommand};
-use event::{key_pressed_with_control, single_key_pressed, Event};
+use event::{key_of_code, mouse_event, Event, Modifiers};
 use std::io::{stdout, Stdout, Write};
 use style::convert_crossterm_color_enum;
 use style::Color;
@@ -45,6 +44,14 @@ impl Terminal {
         terminal::disable_raw_mode().unwrap();
     }
 
+    // mouse
+    pub fn enable_mouse_capture(&mut self) {
+        self.queue(crossterm::event::EnableMouseCapture);
+    }
+    pub fn disable_mouse_capture(&mut self) {
+        self.queue(crossterm::event::DisableMouseCapture);
+    }
+
     // display
     // print
     pub fn print(&mut self, impl_display: impl std::fmt::Display) {
@@ -124,7 +131,7 @@ impl Terminal {
         self.size.get_rows()
     }
     pub fn get_size(&self) -> Size {
-        self.size.clone()
+        self.size
     }
     /// Get last visible row position.
     pub fn get_last_row(&self) -> usize {
@@ -136,22 +143,22 @@ impl Terminal {
     }
 
     // events
+    // Waits at most `timeout` for an event, returns true if one can be read without blocking.
+    pub fn poll_event(&self, timeout: std::time::Duration) -> bool {
+        crossterm::event::poll(timeout).unwrap_or(false)
+    }
     pub fn read_event(&mut self) -> Event {
         match crossterm::event::read().unwrap() {
-            CrosstermEvent::Key(key_event)
-                if ((key_event.modifiers == KeyModifiers::NONE) || (key_event.modifiers == KeyModifiers::SHIFT)) =>
-            {
-                single_key_pressed(key_event.code)
-            }
-            CrosstermEvent::Key(key_event) if key_event.modifiers == KeyModifiers::CONTROL && key_event.code != KeyCode::Null => {
-                key_pressed_with_control(key_event.code)
-            }
+            CrosstermEvent::Key(key_event) => match key_of_code(key_event.code) {
+                Some(key) => Event::key_pressed(key, Modifiers::from_crossterm(key_event.modifiers)),
+                None => Event::Unknown,
+            },
+            CrosstermEvent::Mouse(event) => mouse_event(event),
             CrosstermEvent::Resize(cols, rows) => {
                 self.update_size(cols as usize, rows as usize);
                 self.clear_all();
                 Event::WindowResized(cols as usize, rows as usize)
             }
-            _ => Event::Unknown,
         }
     }
     fn update_size(&mut self, cols: usize, rows: usize) {
@@ -159,9 +166,16 @@ impl Terminal {
     }
 }
 
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Drop for Terminal {
     fn drop(&mut self) {
         self.disable_raw_mode();
+        self.disable_mouse_capture();
         self.leave_alternate_screen();
         self.show_cursor();
         self.flush();
@@ -15,10 +15,10 @@ impl Size {
 
     // accessors
     pub fn get_cols(&self) -> usize {
-        self.cols.clone()
+        self.cols
     }
     pub fn get_rows(&self) -> usize {
-        self.rows.clone()
+        self.rows
     }
     pub fn set(&mut self, cols: usize, rows: usize) {
         self.cols = cols;
@@ -1,5 +1,6 @@
 use crossterm::style::Color as CrosstermColor;
 
+#[derive(Clone, Copy)]
 pub enum Color {
     Black,
     Red,
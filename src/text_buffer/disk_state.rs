@@ -0,0 +1,96 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+/* What the file looked like on disk when it was loaded or saved.
+The modification time and size are compared first, the hash of the content
+only when they differ (a file can be touched without being modified). */
+#[derive(Clone, PartialEq, Debug)]
+pub struct DiskState {
+    modified: Option<SystemTime>,
+    size: u64,
+    hash: u64,
+}
+
+// How the file changed on disk since it was loaded or saved.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DiskChange {
+    Modified(DiskState),
+    Deleted,
+}
+
+impl DiskState {
+    // constructor
+    pub fn new(path: &Path, hash: u64) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            hash,
+        })
+    }
+    pub fn hash_bytes(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    /* Compares the file on disk with this state. If the file was only touched,
+    the state is updated to the new modification time and None is returned. */
+    pub fn check(&mut self, path: &Path) -> Option<DiskChange> {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Some(DiskChange::Deleted),
+        };
+        let modified = metadata.modified().ok();
+        if modified == self.modified && metadata.len() == self.size {
+            return None;
+        }
+        let hash = match std::fs::read(path) {
+            Ok(bytes) => Self::hash_bytes(&bytes),
+            Err(_) => return Some(DiskChange::Deleted),
+        };
+        if hash == self.hash {
+            self.modified = modified;
+            self.size = metadata.len();
+            None
+        } else {
+            Some(DiskChange::Modified(Self {
+                modified,
+                size: metadata.len(),
+                hash,
+            }))
+        }
+    }
+}
+
+// Hashes what's written through it, to know the state of a file without reading it back.
+pub struct HashingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    hasher: DefaultHasher,
+}
+
+impl<'a, W: Write> HashingWriter<'a, W> {
+    pub fn new(inner: &'a mut W) -> Self {
+        Self {
+            inner,
+            hasher: DefaultHasher::new(),
+        }
+    }
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+}
+
+impl<W: Write> Write for HashingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.write(&buf[..written]);
+        Ok(written)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,65 @@
+use super::DiskChange;
+use std::io::ErrorKind;
+
+/* Reasons why an existing file couldn't be loaded.
+A missing file isn't one of them: it's a new file, which will be created on save. */
+#[derive(Debug)]
+pub enum LoadError {
+    InvalidUtf8,
+    PermissionDenied,
+    IsADirectory,
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            ErrorKind::InvalidData => Self::InvalidUtf8,
+            ErrorKind::PermissionDenied => Self::PermissionDenied,
+            ErrorKind::IsADirectory => Self::IsADirectory,
+            _ => Self::Io(error),
+        }
+    }
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidUtf8 => write!(f, "not valid utf-8"),
+            Self::PermissionDenied => write!(f, "permission denied"),
+            Self::IsADirectory => write!(f, "is a directory"),
+            Self::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// Reasons why the buffer couldn't be saved. The file on disk is left untouched in every case.
+#[derive(Debug)]
+pub enum SaveError {
+    ReadOnly,
+    NoPath,
+    ModifiedOnDisk(DiskChange),
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ReadOnly => write!(f, "the file failed to load, it won't be overwritten"),
+            Self::NoPath => write!(f, "no file path"),
+            Self::ModifiedOnDisk(DiskChange::Modified(_)) => write!(f, "the file was modified by another program"),
+            Self::ModifiedOnDisk(DiskChange::Deleted) => write!(f, "the file was deleted by another program"),
+            Self::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
@@ -0,0 +1,83 @@
+use super::{Buffer, Change, Position, Range};
+use std::ops::RangeInclusive;
+
+/* A modification of the text, as seen by code which keeps something in sync with the buffer
+(highlighting, markers in the gutter...). Undo, redo and reload emit their edits like any other. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct BufferEdit {
+    // Where the removed text was, in the buffer before the edit.
+    pub removed: Range,
+    pub removed_text: String,
+    // Where the inserted text is, in the buffer after the edit.
+    pub inserted: Range,
+    pub inserted_text: String,
+}
+
+impl BufferEdit {
+    // Rows of the buffer after the edit whose content changed.
+    pub fn changed_rows(&self) -> RangeInclusive<usize> {
+        self.inserted.start.row..=self.inserted.end.row
+    }
+    // Rows following the edit moved by this number of rows, positive if rows were added.
+    pub fn row_shift(&self) -> isize {
+        self.inserted.end.row as isize - self.removed.end.row as isize
+    }
+}
+
+// An observer of the edits, given by `Buffer::observe_edits`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EditObserver(usize);
+
+// The edits not taken yet, a queue for every observer.
+#[derive(Default)]
+pub(super) struct EditQueues {
+    next_observer: usize,
+    queues: Vec<(EditObserver, Vec<BufferEdit>)>,
+}
+
+/* Every observer has its own queue: the edits are queued until it takes them, and an observer
+leaving doesn't change what the others get. Nothing is queued while nobody observes the buffer. */
+impl Buffer {
+    pub fn observe_edits(&mut self) -> EditObserver {
+        let observer = EditObserver(self.edit_queues.next_observer);
+        self.edit_queues.next_observer += 1;
+        self.edit_queues.queues.push((observer, Vec::new()));
+        observer
+    }
+    // The edits not taken yet by the observer are dropped.
+    pub fn stop_observing_edits(&mut self, observer: EditObserver) {
+        self.edit_queues.queues.retain(|(queued_for, _)| *queued_for != observer);
+    }
+    // Returns the edits done since the last call of the observer, in order.
+    pub fn take_edits(&mut self, observer: EditObserver) -> Vec<BufferEdit> {
+        match self.edit_queues.queues.iter_mut().find(|(queued_for, _)| *queued_for == observer) {
+            Some((_, edits)) => std::mem::take(edits),
+            None => Vec::new(),
+        }
+    }
+    pub(super) fn is_observed(&self) -> bool {
+        !self.edit_queues.queues.is_empty()
+    }
+
+    // Where the text replaced by `change` is, before `change` is applied.
+    pub(super) fn range_removed_by(&self, change: &Change) -> Range {
+        Range::new(
+            self.position_at(change.at),
+            self.position_at(change.at + change.removed.chars().count()),
+        )
+    }
+    // Queues `change` once it's applied, `removed` being the range returned by `range_removed_by`.
+    pub(super) fn queue_edit(&mut self, change: &Change, removed: Range) {
+        let start = removed.start;
+        let end: Position = self.position_at(change.at + change.inserted.chars().count());
+        let edit = BufferEdit {
+            removed,
+            removed_text: change.removed.clone(),
+            inserted: Range::new(start, end),
+            inserted_text: change.inserted.clone(),
+        };
+        for (_, edits) in &mut self.edit_queues.queues {
+            edits.push(edit.clone());
+        }
+    }
+}
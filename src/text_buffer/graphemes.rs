@@ -0,0 +1,91 @@
+use super::Buffer;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/* What the user sees as one character may be several chars (a letter and its combining accents,
+an emoji sequence...), and may take two cells of the terminal (CJK, emoji).
+Columns stay indexed by char into the buffer; these helpers move by grapheme cluster
+and convert a column to its position on the screen. A tab spans the cells up to the next tab stop,
+every `tab_width` cells. The other control characters (a lone '\r' of a file mixing line endings...)
+are drawn in caret notation, like ^M, so that the terminal doesn't interpret them. */
+
+// Number of terminal cells taken by a grapheme cluster drawn at `display_col`.
+pub fn grapheme_width(grapheme: &str, display_col: usize, tab_width: usize) -> usize {
+    if grapheme == "\t" {
+        tab_width - display_col % tab_width
+    } else if let Some(notation) = control_notation(grapheme) {
+        notation.len()
+    } else {
+        UnicodeWidthStr::width(grapheme)
+    }
+}
+
+// How a control character other than a tab is drawn: ^@ to ^_ and ^? for the ASCII ones, <80> to <9f> for the others.
+pub fn control_notation(grapheme: &str) -> Option<String> {
+    let mut chars = grapheme.chars();
+    let c = chars.next().filter(|c| c.is_control() && *c != '\t' && chars.next().is_none())?;
+    Some(match c {
+        '\u{7f}' => String::from("^?"),
+        '\0'..='\u{1f}' => format!("^{}", (c as u8 + b'@') as char),
+        _ => format!("<{:02x}>", c as u32),
+    })
+}
+
+impl Buffer {
+    // Column of the next grapheme cluster boundary after `col` (the end of the row at most).
+    pub fn next_grapheme_col(&self, col: usize, row: usize) -> usize {
+        let text = self.borrow_row_at(row).to_string();
+        let mut grapheme_col = 0;
+        for grapheme in text.graphemes(true) {
+            grapheme_col += grapheme.chars().count();
+            if grapheme_col > col {
+                return grapheme_col;
+            }
+        }
+        grapheme_col
+    }
+    // Column of the grapheme cluster boundary before `col` (0 at least).
+    pub fn previous_grapheme_col(&self, col: usize, row: usize) -> usize {
+        let text = self.borrow_row_at(row).to_string();
+        let mut previous_col = 0;
+        let mut grapheme_col = 0;
+        for grapheme in text.graphemes(true) {
+            if grapheme_col >= col {
+                break;
+            }
+            previous_col = grapheme_col;
+            grapheme_col += grapheme.chars().count();
+        }
+        previous_col
+    }
+    // Position on the screen of the column `col`, counted in terminal cells from the start of the row.
+    pub fn display_col_of(&self, col: usize, row: usize, tab_width: usize) -> usize {
+        let text = self.borrow_row_at(row).to_string();
+        let mut grapheme_col = 0;
+        let mut display_col = 0;
+        for grapheme in text.graphemes(true) {
+            if grapheme_col >= col {
+                break;
+            }
+            grapheme_col += grapheme.chars().count();
+            display_col += grapheme_width(grapheme, display_col, tab_width);
+        }
+        display_col
+    }
+    /* Column of the grapheme cluster drawn at `display_col` on the screen
+    (the end of the row if it's shorter), to keep the cursor's visual column when changing rows. */
+    pub fn col_at_display_col(&self, display_col: usize, row: usize, tab_width: usize) -> usize {
+        let text = self.borrow_row_at(row).to_string();
+        let mut grapheme_col = 0;
+        let mut current_display_col = 0;
+        for grapheme in text.graphemes(true) {
+            let width = grapheme_width(grapheme, current_display_col, tab_width);
+            if current_display_col + width > display_col {
+                break;
+            }
+            current_display_col += width;
+            grapheme_col += grapheme.chars().count();
+        }
+        grapheme_col
+    }
+}
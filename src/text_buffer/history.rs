@@ -0,0 +1,99 @@
+/* One modification of the text: `removed` was replaced by `inserted` at the char index `at`.
+Applying the reverse change gives back the text as it was before. */
+#[derive(Clone)]
+pub struct Change {
+    pub at: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl Change {
+    pub fn reverse(&self) -> Self {
+        Self {
+            at: self.at,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+}
+
+// Changes undone and redone together, in the order they were applied.
+pub struct Transaction {
+    changes: Vec<Change>,
+}
+
+impl Transaction {
+    pub fn get_changes(&self) -> &[Change] {
+        &self.changes
+    }
+}
+
+/* Undo/redo stacks. Every change is pushed into the pending transaction,
+which stays open until `commit` is called: that's how consecutive edits are grouped in one undo step.
+Inside a group (a replayed macro), commits are ignored until the group ends. */
+pub struct History {
+    undo_stack: Vec<Transaction>,
+    redo_stack: Vec<Transaction>,
+    pending: Vec<Change>,
+    group_depth: usize,
+}
+
+impl History {
+    // constructor
+    pub fn new() -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            pending: Vec::new(),
+            group_depth: 0,
+        }
+    }
+
+    // record
+    pub fn record(&mut self, change: Change) {
+        self.redo_stack.clear();
+        self.pending.push(change);
+    }
+    pub fn commit(&mut self) {
+        if self.group_depth == 0 {
+            self.close_pending();
+        }
+    }
+    pub fn begin_group(&mut self) {
+        self.commit();
+        self.group_depth += 1;
+    }
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        self.commit();
+    }
+    // Undoing or redoing inside a group cuts it: the pending changes can't stay on top of another step.
+    fn close_pending(&mut self) {
+        if !self.pending.is_empty() {
+            let changes = std::mem::take(&mut self.pending);
+            self.undo_stack.push(Transaction { changes });
+        }
+    }
+
+    // travel
+    pub fn pop_undo(&mut self) -> Option<Transaction> {
+        self.close_pending();
+        self.undo_stack.pop()
+    }
+    pub fn push_redo(&mut self, transaction: Transaction) {
+        self.redo_stack.push(transaction);
+    }
+    pub fn pop_redo(&mut self) -> Option<Transaction> {
+        self.close_pending();
+        self.redo_stack.pop()
+    }
+    pub fn push_undo(&mut self, transaction: Transaction) {
+        self.undo_stack.push(transaction);
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,38 @@
+// Line break written between two rows when the buffer is saved.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /* A file uses CRLF only if every '\n' is preceded by a '\r'.
+    With mixed line breaks, the lonely '\r' are kept as row content, so they're written back as they were. */
+    pub fn detect(text: &str) -> Self {
+        let lf_count = text.matches('\n').count();
+        if lf_count > 0 && text.matches("\r\n").count() == lf_count {
+            Self::CrLf
+        } else {
+            Self::Lf
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+        }
+    }
+    pub fn toggled(&self) -> Self {
+        match self {
+            Self::Lf => Self::CrLf,
+            Self::CrLf => Self::Lf,
+        }
+    }
+}
@@ -1,8 +1,27 @@
+use ropey::{Rope, RopeSlice};
 use std::io::Write;
 use std::path::PathBuf;
 
-mod row_buffer;
-pub use row_buffer::RowBuffer;
+mod disk_state;
+mod error;
+mod events;
+mod graphemes;
+mod history;
+mod line_ending;
+mod position;
+mod save;
+mod search;
+use disk_state::{DiskState, HashingWriter};
+pub use disk_state::DiskChange;
+pub use error::{LoadError, SaveError};
+pub use events::{BufferEdit, EditObserver};
+use events::EditQueues;
+pub use graphemes::{control_notation, grapheme_width};
+pub use save::{write_atomically, write_private_atomically};
+use history::{Change, History};
+pub use line_ending::LineEnding;
+pub use position::{Position, Range};
+pub use search::{SearchDirection, SearchMatch, SearchPattern};
 
 #[derive(Clone, Copy)]
 pub enum BufferStatus {
@@ -10,47 +29,111 @@ pub enum BufferStatus {
     Unsaved,
 }
 
+/* The text is stored into a rope (a balanced tree of utf-8 chunks), so inserting,
+deleting and looking up a row are logarithmic instead of shifting every row.
+Rows are separated by a single '\n' and the last row has no trailing '\n':
+the rope always holds exactly `lenght` rows, an empty buffer being one empty row.
+The file's line breaks and its final newline are stored aside, so that the file is written back byte for byte. */
 pub struct Buffer {
-    data: Vec<RowBuffer>,
+    data: Rope,
     lenght: usize,
     path: Option<PathBuf>,
     status: BufferStatus,
+    history: History,
+    line_ending: LineEnding,
+    final_newline: bool,
+    // A buffer whose file failed to load is read-only, so that saving it can't clobber the file.
+    read_only: bool,
+    // Incremented by every modification of the text, to know if something changed since a given time.
+    version: usize,
+    // State of the file when it was loaded or saved, to detect that another program modified it.
+    disk_state: Option<DiskState>,
+    // Edits waiting to be taken by the observers of the buffer.
+    edit_queues: EditQueues,
 }
 
 impl Buffer {
     // constructors
-    pub fn new_from_file(file_path: PathBuf) -> Self {
-        let data = std::fs::read_to_string(&file_path)
-            .map(|string| {
-                let mut vec: Vec<RowBuffer> = Vec::new();
-                for line in string.lines() {
-                    vec.push(RowBuffer::new_from_str(line.trim_end()));
-                }
-                vec
-            })
-            .unwrap_or(vec![RowBuffer::new_empty()]);
+    /* A file which doesn't exist yet gives an empty buffer, which will create the file on save.
+    Any other failure is returned: the file exists but its content couldn't be read. */
+    pub fn new_from_file(file_path: PathBuf) -> Result<Self, LoadError> {
+        if file_path.is_dir() {
+            return Err(LoadError::IsADirectory);
+        }
+        let mut buffer = match std::fs::read(&file_path) {
+            Ok(bytes) => {
+                let hash = DiskState::hash_bytes(&bytes);
+                let string = String::from_utf8(bytes).map_err(|_| LoadError::InvalidUtf8)?;
+                let mut buffer = Self::new_from_str(&string);
+                buffer.disk_state = DiskState::new(&file_path, hash);
+                buffer
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Self::new_empty(),
+            Err(error) => return Err(LoadError::from(error)),
+        };
+        buffer.path = Some(file_path);
+        buffer.set_status(BufferStatus::Saved);
+        Ok(buffer)
+    }
+    /* An empty buffer standing for a file which failed to load: it keeps the path but can't be saved to it,
+    so it has nothing unsaved and the file on disk isn't watched. */
+    pub fn new_read_only(file_path: PathBuf) -> Self {
+        let mut buffer = Self::new_empty();
+        buffer.path = Some(file_path);
+        buffer.read_only = true;
+        buffer.set_status(BufferStatus::Saved);
+        buffer
+    }
+    // The line ending style and the final newline are deduced from `text`.
+    pub fn new_from_str(text: &str) -> Self {
+        let line_ending = LineEnding::detect(text);
+        let mut text = match line_ending {
+            LineEnding::Lf => text.to_string(),
+            LineEnding::CrLf => text.replace("\r\n", "\n"),
+        };
+        let final_newline = text.ends_with('\n');
+        if final_newline {
+            text.pop();
+        }
+        let data = Rope::from_str(&text);
         Self {
-            lenght: data.len(),
+            lenght: data.len_lines(),
             data,
-            path: Some(file_path),
-            status: BufferStatus::Saved,
+            path: None,
+            status: BufferStatus::Unsaved,
+            history: History::new(),
+            line_ending,
+            final_newline,
+            read_only: false,
+            version: 0,
+            disk_state: None,
+            edit_queues: EditQueues::default(),
         }
     }
     pub fn new_empty() -> Self {
         Self {
-            data: vec![RowBuffer::new_empty()],
+            data: Rope::new(),
             lenght: 1,
             path: None,
             status: BufferStatus::Unsaved,
+            history: History::new(),
+            line_ending: LineEnding::default(),
+            final_newline: true,
+            read_only: false,
+            version: 0,
+            disk_state: None,
+            edit_queues: EditQueues::default(),
         }
     }
 
     // to print
-    pub fn borrow_row_at(&self, index: usize) -> &RowBuffer {
-        &self.data[index]
+    /// Borrow the content of a row, without its line break.
+    pub fn borrow_row_at(&self, index: usize) -> RopeSlice<'_> {
+        let start = self.data.line_to_char(index);
+        self.data.slice(start..start + self.get_lenght_of_row(index))
     }
-    pub fn borrow_char_at(&self, col: usize, row: usize) -> &char {
-        self.data[row].borrow_char_at(col)
+    pub fn get_char_at(&self, col: usize, row: usize) -> char {
+        self.data.char(self.char_index_of(col, row))
     }
 
     // accessors
@@ -58,63 +141,162 @@ impl Buffer {
         self.lenght
     }
     pub fn get_lenght_of_row(&self, index: usize) -> usize {
-        self.data[index].get_lenght()
+        let line = self.data.line(index);
+        let lenght = line.len_chars();
+        if lenght > 0 && line.char(lenght - 1) == '\n' {
+            lenght - 1
+        } else {
+            lenght
+        }
     }
     pub fn get_path(&self) -> Option<PathBuf> {
         self.path.clone()
     }
     pub fn get_path_as_str(&self) -> Option<&str> {
         match &self.path {
-            Some(path) => match path.to_str() {
-                Some(s) => Some(s),
-                None => None,
-            },
+            Some(path) => path.to_str(),
             None => None,
         }
     }
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.data.len_chars() == 0
     }
     pub fn row_is_empty(&self, index: usize) -> bool {
-        self.data[index].is_empty()
+        self.get_lenght_of_row(index) == 0
+    }
+    pub fn get_version(&self) -> usize {
+        self.version
+    }
+    pub fn has_unsaved_changes(&self) -> bool {
+        matches!(self.status, BufferStatus::Unsaved)
+    }
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+    pub fn get_line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        if self.line_ending != line_ending {
+            self.line_ending = line_ending;
+            self.set_status(BufferStatus::Unsaved);
+        }
+    }
+    pub fn has_final_newline(&self) -> bool {
+        self.final_newline
+    }
+    pub fn set_final_newline(&mut self, final_newline: bool) {
+        if self.final_newline != final_newline {
+            self.final_newline = final_newline;
+            self.set_status(BufferStatus::Unsaved);
+        }
     }
     // status accessors
     pub fn get_status(&self) -> BufferStatus {
-        self.status.clone()
+        self.status
     }
     fn set_status(&mut self, new_status: BufferStatus) {
         self.status = new_status;
     }
 
     // write
-    pub fn save(&mut self) -> BufferStatus {
-        if let Some(path) = &self.path {
-            let file = std::fs::File::create(path);
-            match file {
-                Ok(mut fp) => {
-                    for i in 0..self.get_lenght() {
-                        write!(fp, "{}\n", self.borrow_row_at(i)).unwrap();
+    /* The file is replaced atomically: after a failure, it's left as it was before.
+    Saving is refused if another program modified the file since it was loaded or saved. */
+    pub fn save(&mut self) -> Result<(), SaveError> {
+        if self.read_only {
+            return Err(SaveError::ReadOnly);
+        }
+        if let Some(change) = self.check_disk() {
+            return Err(SaveError::ModifiedOnDisk(change));
+        }
+        self.save_overwriting()
+    }
+    // Saves even if the file was modified on disk by another program.
+    pub fn save_overwriting(&mut self) -> Result<(), SaveError> {
+        if self.read_only {
+            return Err(SaveError::ReadOnly);
+        }
+        let path = self.path.as_ref().ok_or(SaveError::NoPath)?;
+        let mut hash = 0;
+        save::write_atomically(path, |writer| {
+            let mut writer = HashingWriter::new(writer);
+            self.write_to(&mut writer)?;
+            hash = writer.finish();
+            Ok(())
+        })?;
+        self.disk_state = DiskState::new(path, hash);
+        self.set_status(BufferStatus::Saved);
+        Ok(())
+    }
+    // Tells if the file changed on disk since it was loaded or saved by this buffer.
+    pub fn check_disk(&mut self) -> Option<DiskChange> {
+        match (&self.path, &mut self.disk_state) {
+            (Some(path), Some(disk_state)) => disk_state.check(path),
+            // A new file which appeared on disk meanwhile, not the one which failed to load.
+            (Some(path), None) if !self.read_only && path.exists() => DiskState::new(path, 0).map(DiskChange::Modified),
+            _ => None,
+        }
+    }
+    /* Replaces the text by the content of the file on disk. It's one undoable step,
+    so the edits discarded by a reload can be brought back. */
+    pub fn reload(&mut self) -> Result<(), LoadError> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => return Ok(()),
+        };
+        let reloaded = Self::new_from_file(path)?;
+        self.replace_text_with(&reloaded);
+        self.disk_state = reloaded.disk_state;
+        self.set_status(BufferStatus::Saved);
+        Ok(())
+    }
+    /* Replaces the text as one undoable step, the buffer staying the one of its file:
+    the edits recovered from a swap file are unsaved edits of the file as it was loaded. */
+    pub fn replace_text(&mut self, text: &str) {
+        self.replace_text_with(&Self::new_from_str(text));
+        self.set_status(BufferStatus::Unsaved);
+    }
+    fn replace_text_with(&mut self, other: &Self) {
+        self.history.commit();
+        self.edit(0, self.data.len_chars(), &other.data.to_string());
+        self.history.commit();
+        self.line_ending = other.line_ending;
+        self.final_newline = other.final_newline;
+    }
+    // Writes the rows separated by the buffer's line ending, as they will be stored on disk.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for chunk in self.data.chunks() {
+            match self.line_ending {
+                LineEnding::Lf => writer.write_all(chunk.as_bytes())?,
+                LineEnding::CrLf => {
+                    for (i, piece) in chunk.split('\n').enumerate() {
+                        if i > 0 {
+                            writer.write_all(b"\r\n")?;
+                        }
+                        writer.write_all(piece.as_bytes())?;
                     }
-                    self.set_status(BufferStatus::Saved);
-                }
-                Err(_) => {
-                    self.set_status(BufferStatus::Unsaved);
                 }
             }
-        } else {
-            self.set_status(BufferStatus::Unsaved);
         }
-        self.get_status()
+        if self.final_newline {
+            writer.write_all(self.line_ending.as_str().as_bytes())?;
+        }
+        Ok(())
     }
-    pub fn save_as(&mut self, path: PathBuf) -> BufferStatus {
-        self.path = Some(path);
-        if let BufferStatus::Unsaved = self.save() {
-            self.path = None;
-            self.set_status(BufferStatus::Unsaved);
-        } else {
-            self.set_status(BufferStatus::Saved);
+    // Saving to a path chosen by the user is allowed even if the buffer was read-only.
+    // The user chose the path, an existing file there is overwritten.
+    pub fn save_as(&mut self, path: PathBuf) -> Result<(), SaveError> {
+        let previous_path = self.path.replace(path);
+        let previous_read_only = std::mem::replace(&mut self.read_only, false);
+        let result = self.save_overwriting();
+        if result.is_err() {
+            self.path = previous_path;
+            self.read_only = previous_read_only;
         }
-        self.get_status()
+        result
+    }
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
     }
     pub fn clear_path(&mut self) {
         self.path = None;
@@ -122,34 +304,126 @@ impl Buffer {
 
     // manip buf
     pub fn insert_char(&mut self, col: usize, row: usize, c: char) {
-        self.data[row].insert(col, c);
-        self.set_status(BufferStatus::Unsaved);
+        self.edit(self.char_index_of(col, row), 0, &c.to_string());
     }
     pub fn insert_row_at(&mut self, index: usize) {
-        self.data.insert(index, RowBuffer::new_empty());
-        self.lenght += 1;
-        self.set_status(BufferStatus::Unsaved);
+        self.insert_row_at_with_vec(index, Vec::new());
     }
     pub fn insert_row_at_with_vec(&mut self, index: usize, vec: Vec<char>) {
-        self.data.insert(index, RowBuffer::new_from_vec(vec));
-        self.lenght += 1;
-        self.set_status(BufferStatus::Unsaved);
+        let mut text: String = vec.into_iter().collect();
+        if index < self.lenght {
+            text.push('\n');
+            self.edit(self.data.line_to_char(index), 0, &text);
+        } else {
+            text.insert(0, '\n');
+            self.edit(self.data.len_chars(), 0, &text);
+        }
     }
+    // Nothing is deleted at the end of the row: the line break isn't a char of the row.
     pub fn delete_char(&mut self, col: usize, row: usize) {
-        self.data[row].delete(col);
-        self.set_status(BufferStatus::Unsaved);
+        if col < self.get_lenght_of_row(row) {
+            self.edit(self.char_index_of(col, row), 1, "");
+        }
     }
     pub fn remove_row_from(&mut self, col: usize, row: usize) -> Vec<char> {
-        self.set_status(BufferStatus::Unsaved);
-        self.data[row].remove_from(col)
+        let removed = self.edit(self.char_index_of(col, row), self.get_lenght_of_row(row) - col, "");
+        removed.chars().collect()
     }
+    /* Removes the row and its line break. The buffer always keeps at least one row,
+    so removing the only one just empties it. */
     pub fn remove_row_to_get_data(&mut self, index: usize) -> Vec<char> {
-        self.set_status(BufferStatus::Unsaved);
-        self.lenght -= 1;
-        self.data.remove(index).unwrap_to_get_data()
+        let removed = self.borrow_row_at(index).chars().collect();
+        let start = self.data.line_to_char(index);
+        if index + 1 < self.lenght {
+            self.edit(start, self.data.line_to_char(index + 1) - start, "");
+        } else if index > 0 {
+            self.edit(start - 1, self.data.len_chars() - start + 1, "");
+        } else {
+            self.edit(0, self.data.len_chars(), "");
+        }
+        removed
     }
     pub fn push_vec_to_row(&mut self, index: usize, vec: &mut Vec<char>) {
-        self.data[index].append_mb_vec_at_end(vec);
+        let text: String = vec.drain(..).collect();
+        self.edit(self.char_index_of(self.get_lenght_of_row(index), index), 0, &text);
+    }
+
+    // history
+    /* Closes the current undo step: the edits done until now will be undone together,
+    the next ones will belong to a new step. */
+    pub fn commit_transaction(&mut self) {
+        self.history.commit();
+    }
+    /* Everything done until `end_undo_group` is undone in one step, whatever commits the transaction
+    in between. Groups may be nested. */
+    pub fn begin_undo_group(&mut self) {
+        self.history.begin_group();
+    }
+    pub fn end_undo_group(&mut self) {
+        self.history.end_group();
+    }
+    // Returns the (col, row) position where the undone change happened.
+    pub fn undo(&mut self) -> Option<(usize, usize)> {
+        let transaction = self.history.pop_undo()?;
+        for change in transaction.get_changes().iter().rev() {
+            self.apply(&change.reverse());
+        }
+        let position = transaction.get_changes().first().map(|change| self.position_of(change.at));
+        self.history.push_redo(transaction);
         self.set_status(BufferStatus::Unsaved);
+        position
+    }
+    // Returns the (col, row) position right after the redone change.
+    pub fn redo(&mut self) -> Option<(usize, usize)> {
+        let transaction = self.history.pop_redo()?;
+        for change in transaction.get_changes().iter() {
+            self.apply(change);
+        }
+        let position = transaction
+            .get_changes()
+            .last()
+            .map(|change| self.position_of(change.at + change.inserted.chars().count()));
+        self.history.push_undo(transaction);
+        self.set_status(BufferStatus::Unsaved);
+        position
+    }
+
+    /* Every modification of the buffer goes through here: `lenght` chars are replaced by `text` at `at`,
+    and the change is recorded into the history. Returns the removed text. */
+    pub(crate) fn edit(&mut self, at: usize, lenght: usize, text: &str) -> String {
+        let change = Change {
+            at,
+            removed: self.data.slice(at..at + lenght).to_string(),
+            inserted: text.to_string(),
+        };
+        self.apply(&change);
+        self.set_status(BufferStatus::Unsaved);
+        let removed = change.removed.clone();
+        self.history.record(change);
+        removed
+    }
+    fn apply(&mut self, change: &Change) {
+        let removed_range = self.is_observed().then(|| self.range_removed_by(change));
+        let removed_lenght = change.removed.chars().count();
+        if removed_lenght > 0 {
+            self.data.remove(change.at..change.at + removed_lenght);
+        }
+        if !change.inserted.is_empty() {
+            self.data.insert(change.at, &change.inserted);
+        }
+        self.lenght = self.data.len_lines();
+        self.version += 1;
+        if let Some(removed_range) = removed_range {
+            self.queue_edit(change, removed_range);
+        }
+    }
+
+    // conversions
+    pub(crate) fn char_index_of(&self, col: usize, row: usize) -> usize {
+        self.data.line_to_char(row) + col
+    }
+    pub(crate) fn position_of(&self, char_index: usize) -> (usize, usize) {
+        let row = self.data.char_to_line(char_index);
+        (char_index - self.data.line_to_char(row), row)
     }
 }
@@ -0,0 +1,90 @@
+use super::Buffer;
+
+/* A place into the buffer, between two chars. `col` counts chars from the start of the row,
+the end of a row (col == lenght of the row) is the place just before its line break.
+Positions are ordered as they appear into the text. */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct Position {
+    // `row` is declared first so that the derived order compares it first.
+    pub row: usize,
+    pub col: usize,
+}
+
+// The text between two positions: `start` is included, `end` is excluded. It may span several rows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Position {
+    // constructor
+    pub fn new(col: usize, row: usize) -> Self {
+        Self { row, col }
+    }
+}
+
+impl Range {
+    // constructor
+    // The two positions may be given in any order.
+    pub fn new(a: Position, b: Position) -> Self {
+        Self {
+            start: a.min(b),
+            end: a.max(b),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+    pub fn contains(&self, position: Position) -> bool {
+        self.start <= position && position < self.end
+    }
+}
+
+/* Edits between positions, splitting and joining rows as the text requires.
+Positions beyond the end of a row or of the buffer are moved back to the nearest end.
+Inserted text may use "\r\n" line breaks, they are stored as '\n' like the rest of the buffer. */
+impl Buffer {
+    pub fn insert_str(&mut self, position: Position, text: &str) -> Position {
+        self.replace_range(Range::new(position, position), text)
+    }
+    // Returns the removed text, rows being separated by '\n'.
+    pub fn delete_range(&mut self, range: Range) -> String {
+        let start = self.char_index_at(range.start);
+        let end = self.char_index_at(range.end);
+        self.edit(start, end - start, "")
+    }
+    // Returns the position right after the inserted text.
+    pub fn replace_range(&mut self, range: Range, text: &str) -> Position {
+        let text = text.replace("\r\n", "\n");
+        let start = self.char_index_at(range.start);
+        let end = self.char_index_at(range.end);
+        if start != end || !text.is_empty() {
+            self.edit(start, end - start, &text);
+        }
+        self.position_at(start + text.chars().count())
+    }
+    pub fn slice(&self, range: Range) -> String {
+        let start = self.char_index_at(range.start);
+        let end = self.char_index_at(range.end);
+        self.data.slice(start..end).to_string()
+    }
+    // Position of the end of the last row.
+    pub fn end_position(&self) -> Position {
+        self.clamp_position(Position::new(usize::MAX, usize::MAX))
+    }
+    pub fn clamp_position(&self, position: Position) -> Position {
+        let row = position.row.min(self.lenght - 1);
+        Position::new(position.col.min(self.get_lenght_of_row(row)), row)
+    }
+
+    // conversions
+    pub(crate) fn char_index_at(&self, position: Position) -> usize {
+        let position = self.clamp_position(position);
+        self.char_index_of(position.col, position.row)
+    }
+    pub(crate) fn position_at(&self, char_index: usize) -> Position {
+        let (col, row) = self.position_of(char_index);
+        Position::new(col, row)
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Symbolic links are followed at most this number of times, like the kernel does.
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/* Writes a file without ever leaving it half written: the data goes into a temporary file
+of the same directory, which is synced then renamed over the target.
+The target's permissions are kept, and if it's a symbolic link the file it points to is replaced, not the link. */
+pub fn write_atomically<F>(path: &Path, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    write_atomically_with(path, false, write)
+}
+/* Same, but the file is only readable and writable by its owner, whatever its permissions were:
+a swap file holds the edits of files which may be private. */
+pub fn write_private_atomically<F>(path: &Path, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    write_atomically_with(path, true, write)
+}
+
+fn write_atomically_with<F>(path: &Path, private: bool, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    let target = resolve_symlinks(path)?;
+    let directory = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = target.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let temporary_path = directory.join(format!(".{}.ante-save-{}", file_name, std::process::id()));
+
+    let result = write_temporary_file(&target, &temporary_path, private, write)
+        .and_then(|_| std::fs::rename(&temporary_path, &target));
+    match result {
+        Ok(_) => {
+            // The rename itself is durable once the directory is synced (not possible on every platform).
+            if let Ok(directory) = File::open(&directory) {
+                let _ = directory.sync_all();
+            }
+            Ok(())
+        }
+        Err(error) => {
+            let _ = std::fs::remove_file(&temporary_path);
+            Err(error)
+        }
+    }
+}
+
+fn write_temporary_file<F>(target: &Path, temporary_path: &Path, private: bool, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    // A temporary file left by a previous crash is replaced.
+    let _ = std::fs::remove_file(temporary_path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    // Created private rather than restricted afterwards, so that it's never readable by others.
+    #[cfg(unix)]
+    {
+        if private {
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        }
+    }
+    let file = options.open(temporary_path)?;
+    if !private {
+        if let Ok(metadata) = std::fs::metadata(target) {
+            file.set_permissions(metadata.permissions())?;
+        }
+    }
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()
+}
+
+// Follows the symbolic links until a regular (or not yet existing) file is reached.
+fn resolve_symlinks(path: &Path) -> std::io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_DEPTH {
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = std::fs::read_link(&path)?;
+                path = match path.parent() {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(std::io::Error::other("too many levels of symbolic links"))
+}
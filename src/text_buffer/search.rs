@@ -0,0 +1,195 @@
+use super::{Buffer, Position, Range};
+use regex::{Regex, RegexBuilder};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+// A literal text or a regular expression to look for, compiled once for every search.
+#[derive(Clone)]
+pub struct SearchPattern {
+    regex: Regex,
+    is_regex: bool,
+    case_sensitive: bool,
+}
+
+// Position of a match into the buffer, in chars. A match never spans several rows.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SearchMatch {
+    pub row: usize,
+    pub start_col: usize,
+    pub end_col: usize,
+}
+
+impl SearchPattern {
+    // constructor
+    pub fn new(pattern: &str, is_regex: bool, case_sensitive: bool) -> Result<Self, regex::Error> {
+        let source = if is_regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        Ok(Self {
+            regex: RegexBuilder::new(&source).case_insensitive(!case_sensitive).build()?,
+            is_regex,
+            case_sensitive,
+        })
+    }
+
+    // accessors
+    pub fn get_regex(&self) -> &Regex {
+        &self.regex
+    }
+    pub fn is_regex(&self) -> bool {
+        self.is_regex
+    }
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /* Text replacing a match: with a regex, `$1` or `${name}` in `replacement` are replaced
+    by the corresponding capture group, a literal pattern takes `replacement` as it is. */
+    pub fn expand_replacement(&self, row_text: &str, found: &SearchMatch, replacement: &str) -> String {
+        if !self.is_regex {
+            return replacement.to_string();
+        }
+        let byte_start = row_text
+            .char_indices()
+            .nth(found.start_col)
+            .map_or(row_text.len(), |(index, _)| index);
+        let mut expanded = String::new();
+        if let Some(captures) = self.regex.captures_at(row_text, byte_start) {
+            captures.expand(replacement, &mut expanded);
+        }
+        expanded
+    }
+
+    // Matches of a row, in order. Empty matches are skipped: there's nothing to go to.
+    fn find_in_row(&self, row: usize, text: &str) -> Vec<SearchMatch> {
+        self.regex
+            .find_iter(text)
+            .filter(|found| !found.as_str().is_empty())
+            .map(|found| {
+                let start_col = text[..found.start()].chars().count();
+                SearchMatch {
+                    row,
+                    start_col,
+                    end_col: start_col + found.as_str().chars().count(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Buffer {
+    pub fn find_all(&self, pattern: &SearchPattern) -> Vec<SearchMatch> {
+        (0..self.get_lenght())
+            .flat_map(|row| pattern.find_in_row(row, &self.borrow_row_at(row).to_string()))
+            .collect()
+    }
+    // Returns the first match starting at or after (col, row), without wrapping around.
+    pub fn find_from(&self, pattern: &SearchPattern, col: usize, row: usize) -> Option<SearchMatch> {
+        (row..self.get_lenght()).find_map(|current_row| {
+            pattern
+                .find_in_row(current_row, &self.borrow_row_at(current_row).to_string())
+                .into_iter()
+                .find(|found| current_row > row || found.start_col >= col)
+        })
+    }
+    /* Replaces a match found with `pattern` (see `SearchPattern::expand_replacement`).
+    Returns the (col, row) position right after the inserted text. */
+    pub fn replace_match(&mut self, pattern: &SearchPattern, found: &SearchMatch, replacement: &str) -> (usize, usize) {
+        let row_text = self.borrow_row_at(found.row).to_string();
+        let text = pattern.expand_replacement(&row_text, found, replacement);
+        let range = Range::new(
+            Position::new(found.start_col, found.row),
+            Position::new(found.end_col, found.row),
+        );
+        let end = self.replace_range(range, &text);
+        (end.col, end.row)
+    }
+    // The matches of a row, in order, with the text they were found into.
+    pub fn find_in_row(&self, pattern: &SearchPattern, row: usize) -> (String, Vec<SearchMatch>) {
+        let text = self.borrow_row_at(row).to_string();
+        let matches = pattern.find_in_row(row, &text);
+        (text, matches)
+    }
+    /* Replaces matches of a row found into `row_text`, the text of the row before any of them was replaced:
+    a replaced text is never searched again, and the captures are taken from the original text.
+    The replacements made before them on the row moved them by `shift` chars.
+    Returns how many chars the row grew by. */
+    pub fn replace_row_matches(
+        &mut self,
+        pattern: &SearchPattern,
+        row_text: &str,
+        matches: &[SearchMatch],
+        shift: isize,
+        replacement: &str,
+    ) -> isize {
+        let mut growth = 0;
+        // From right to left, so that the positions of the next ones stay valid.
+        for found in matches.iter().rev() {
+            let text = pattern.expand_replacement(row_text, found, replacement);
+            let col = |col: usize| (col as isize + shift) as usize;
+            self.replace_range(
+                Range::new(
+                    Position::new(col(found.start_col), found.row),
+                    Position::new(col(found.end_col), found.row),
+                ),
+                &text,
+            );
+            growth += text.chars().count() as isize - (found.end_col - found.start_col) as isize;
+        }
+        growth
+    }
+    /* Replaces every match from (col, row) to the end of the buffer, returns how many there were.
+    The matches are those of the text before replacing anything.
+    The replacements belong to the current undo step: commit the transaction before and after
+    to undo them in one step. */
+    pub fn replace_all_from(&mut self, pattern: &SearchPattern, replacement: &str, col: usize, row: usize) -> usize {
+        let mut count = 0;
+        // From the last row, a replacement with a line break moves only the rows already done.
+        for current_row in (row..self.get_lenght()).rev() {
+            let (text, mut matches) = self.find_in_row(pattern, current_row);
+            if current_row == row {
+                matches.retain(|found| found.start_col >= col);
+            }
+            self.replace_row_matches(pattern, &text, &matches, 0, replacement);
+            count += matches.len();
+        }
+        count
+    }
+
+    /* Returns the first match after (forward) or before (backward) the (col, row) position,
+    wrapping around the end of the buffer. */
+    pub fn search(
+        &self,
+        pattern: &SearchPattern,
+        col: usize,
+        row: usize,
+        direction: SearchDirection,
+    ) -> Option<SearchMatch> {
+        let lenght = self.get_lenght();
+        let row = row.min(lenght - 1);
+        // The starting row is visited twice: for the matches after the position, and after wrapping around.
+        for step in 0..=lenght {
+            let current_row = match direction {
+                SearchDirection::Forward => (row + step) % lenght,
+                SearchDirection::Backward => (row + lenght * 2 - step) % lenght,
+            };
+            let matches = pattern.find_in_row(current_row, &self.borrow_row_at(current_row).to_string());
+            let found = match (direction, step) {
+                (SearchDirection::Forward, 0) => matches.into_iter().find(|found| found.start_col > col),
+                (SearchDirection::Forward, _) => matches.into_iter().next(),
+                (SearchDirection::Backward, 0) => matches.into_iter().rev().find(|found| found.start_col < col),
+                (SearchDirection::Backward, _) => matches.into_iter().next_back(),
+            };
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+}